@@ -0,0 +1,155 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+use tokio::task::JoinSet;
+
+use crate::{
+    gofer::fetch_chapters,
+    structs::{Chapter, Target},
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Fetches and parses every selected target (all enabled ones if `names` is empty),
+/// printing the chapters found for each one without touching the database or Discord.
+/// Fails if any target could not be fetched or did not yield a single chapter.
+pub async fn check_targets(targets: Vec<Target>, names: &[String]) -> Result<()> {
+    for name in names {
+        if !targets.iter().any(|target| &target.name == name) {
+            bail!("No target named \"{}\".", name);
+        }
+    }
+
+    let targets: Vec<Target> = targets
+        .into_iter()
+        .filter(|target| match names.is_empty() {
+            true => target.enabled,
+            false => names.contains(&target.name),
+        })
+        .collect();
+
+    let mut handles = JoinSet::new();
+    for (index, target) in targets.iter().cloned().enumerate() {
+        handles.spawn(async move { (index, fetch_chapters(&target, None).await) });
+    }
+
+    let mut results = vec![];
+    while let Some(result) = handles.join_next().await {
+        results.push(result?);
+    }
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut failures = 0;
+    for (index, result) in results {
+        let target = &targets[index];
+        match result {
+            Ok(chapters) if chapters.is_empty() => {
+                failures += 1;
+                println!("{} {}: no chapters found", "[FAIL]".red(), target.name);
+            }
+            Ok(chapters) => {
+                println!(
+                    "{} {}: {} chapters",
+                    "[ OK ]".green(),
+                    target.name,
+                    chapters.len()
+                );
+                println!("{}", format_chapters_table(&chapters));
+            }
+            Err(error) => {
+                failures += 1;
+                println!("{} {}: {}", "[FAIL]".red(), target.name, error);
+            }
+        }
+        println!();
+    }
+
+    if failures > 0 {
+        bail!(
+            "{} of {} targets failed the check.",
+            failures,
+            targets.len()
+        );
+    }
+
+    println!("All {} targets passed the check.", targets.len());
+    Ok(())
+}
+
+/// Lays out chapters as a table with aligned columns.
+fn format_chapters_table(chapters: &[Chapter]) -> String {
+    let header = ["NUMBER", "TITLE", "DATE", "URL", "ANNOUNCED AT"].map(String::from);
+    let mut rows = vec![header];
+    for chapter in chapters {
+        rows.push([
+            chapter.number.to_owned(),
+            // Keep multi-line titles on their row
+            chapter
+                .title
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            chapter.date.format(DATE_FORMAT).to_string(),
+            chapter.url.to_owned(),
+            chapter.announced_at.format(DATE_FORMAT).to_string(),
+        ]);
+    }
+
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(cell.chars().count());
+        }
+    }
+
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let padding = widths[column] - cell.chars().count();
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect();
+            cells.join("  ").trim_end().to_owned()
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::DateTime;
+
+    use crate::structs::Chapter;
+
+    use super::format_chapters_table;
+
+    #[test]
+    fn test_format_chapters_table() {
+        let date = DateTime::parse_from_rfc3339("2022-09-16T03:00:00+00:00")
+            .unwrap()
+            .into();
+        let chapters = vec![Chapter {
+            manga: "Test Manga".into(),
+            number: "00023".into(),
+            title: "Part 23:\n   The Alpha".into(),
+            date,
+            url: "https://comic-rss.com/episode/00023".into(),
+            thumbnail: None,
+            logged_at: None,
+            announced_at: date,
+        }];
+
+        assert_eq!(
+            format_chapters_table(&chapters),
+            [
+                "NUMBER  TITLE               DATE                 URL                                  ANNOUNCED AT",
+                "00023   Part 23: The Alpha  2022-09-16 03:00:00  https://comic-rss.com/episode/00023  2022-09-16 03:00:00",
+            ]
+            .join("\n"),
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, bail, Result};
+
+/// Text printed by `--help`.
+pub const USAGE: &str = "Usage: decatholac-mango-tarnished [OPTIONS] [COMMAND]
+
+Commands:
+  check-targets [NAME...]  Fetch and print the chapters of all (or the named) targets
+                           without touching the database or Discord
+
+Options:
+  -c, --config <path>      Settings file to load (default: settings.toml)
+  -d, --database <path>    SQLite database file to use (default: database.db)
+  -t, --token-file <path>  Read the Discord token from a file instead of the settings
+  -1s, --one-shot          Run the workers once and quit instead of standing by
+      --print-config-schema
+                           Print the JSON Schema of the settings file and quit
+  -h, --help               Print this message
+
+Settings keys can also be overridden with environment variables:
+  DMT_TOKEN, DMT_CRON";
+
+/// What the executable should do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Run as a Discord bot (the default).
+    Run,
+    /// Fetch the given targets (or all of them if empty) and print the parsed chapters.
+    CheckTargets(Vec<String>),
+}
+
+/// Options given through the command-line arguments.
+pub struct Flags {
+    pub command: Command,
+    pub one_shot: bool,
+    pub help: bool,
+    pub print_config_schema: bool,
+    pub config: String,
+    pub database: String,
+    pub token_file: Option<String>,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            command: Command::Run,
+            one_shot: false,
+            help: false,
+            print_config_schema: false,
+            config: String::from("settings.toml"),
+            database: String::from("database.db"),
+            token_file: None,
+        }
+    }
+}
+
+/// Parses the command-line arguments (without the executable name) into `Flags`.
+/// Options that take a value accept both `--option value` and `--option=value`.
+pub fn parse_flags(args: impl IntoIterator<Item = String>) -> Result<Flags> {
+    let mut flags = Flags::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_owned(), Some(value.to_owned()))
+            }
+            _ => (arg.clone(), None),
+        };
+
+        if inline_value.is_some() && !takes_value(&name) {
+            bail!("{} does not take a value.", name);
+        }
+
+        let mut take_value = || -> Result<String> {
+            match inline_value.clone().or_else(|| args.next()) {
+                Some(value) if !value.is_empty() => Ok(value),
+                _ => Err(anyhow!("Missing value for {}.", name)),
+            }
+        };
+
+        match name.as_str() {
+            "--oneshot" | "--one-shot" | "-1s" => flags.one_shot = true,
+            "--help" | "-h" => flags.help = true,
+            "--print-config-schema" => flags.print_config_schema = true,
+            "--config" | "-c" => flags.config = take_value()?,
+            "--database" | "-d" => flags.database = take_value()?,
+            "--token-file" | "-t" => flags.token_file = Some(take_value()?),
+            "check-targets" if flags.command == Command::Run => {
+                flags.command = Command::CheckTargets(vec![])
+            }
+            other if other.starts_with('-') => {
+                bail!("Unknown argument: {}. See --help for usage.", other)
+            }
+            other => match &mut flags.command {
+                Command::CheckTargets(names) => names.push(other.to_owned()),
+                Command::Run => bail!("Unknown command: {}. See --help for usage.", other),
+            },
+        }
+    }
+
+    Ok(flags)
+}
+
+fn takes_value(name: &str) -> bool {
+    matches!(
+        name,
+        "--config" | "-c" | "--database" | "-d" | "--token-file" | "-t"
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_flags, Command};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let flags = parse_flags(args(&[])).unwrap();
+        assert!(!flags.one_shot);
+        assert_eq!(flags.config, "settings.toml");
+        assert_eq!(flags.database, "database.db");
+        assert!(flags.token_file.is_none());
+
+        let flags = parse_flags(args(&[
+            "--one-shot",
+            "--config",
+            "staging.toml",
+            "--database=staging.db",
+            "-t",
+            "/run/secrets/token",
+        ]))
+        .unwrap();
+        assert!(flags.one_shot);
+        assert_eq!(flags.config, "staging.toml");
+        assert_eq!(flags.database, "staging.db");
+        assert_eq!(flags.token_file.unwrap(), "/run/secrets/token");
+
+        let flags =
+            parse_flags(args(&["check-targets", "Bokuyaba", "-c", "staging.toml"])).unwrap();
+        assert_eq!(
+            flags.command,
+            Command::CheckTargets(vec!["Bokuyaba".into()])
+        );
+        assert_eq!(flags.config, "staging.toml");
+
+        assert!(parse_flags(args(&["--verbose"])).is_err());
+        assert!(parse_flags(args(&["Bokuyaba"])).is_err());
+        assert!(parse_flags(args(&["--config"])).is_err());
+        assert!(parse_flags(args(&["--one-shot=yes"])).is_err());
+    }
+}
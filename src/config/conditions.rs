@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+
+use chrono::DateTime;
+use regex::Regex;
+use serde_json::json;
+use toml::{map::Map, Value as TomlValue};
+
+use crate::{
+    parsers::json_path::JsonPath,
+    structs::{DateOperand, JsonCondition, JsonDateTimeFormat, ValueTest},
+};
+
+use super::reader::TableReader;
+
+/// Operators of a value test, e.g. `status = { ne = "public" }`.
+pub const TEST_OPERATORS: [&str; 11] = [
+    "eq", "ne", "exists", "missing", "matches", "lt", "le", "gt", "ge", "before", "after",
+];
+
+/// Reads a table of conditions on JSON chapters, like `skip` and `include` of the keys.
+/// Every key is a path whose value has to be equal to the given one (`readable = false`),
+/// or pass a test (`"episode.publish_start" = { after = "now" }`).
+/// `any` and `all` group arrays of such tables, each holding if all of its conditions hold.
+/// Returns `None` if any condition is invalid.
+pub fn read_conditions(reader: &TableReader, key: &str) -> Option<Vec<JsonCondition>> {
+    let table = match reader.get(key) {
+        Some(TomlValue::Table(table)) => table,
+        Some(_) => {
+            reader.error(key, "must be a table of conditions");
+            return None;
+        }
+        None => return Some(vec![]),
+    };
+
+    let mut valid = true;
+    let conditions = read_condition_table(table, key, &mut |location, message| {
+        reader.error(location, message);
+        valid = false;
+    });
+
+    valid.then_some(conditions)
+}
+
+fn read_condition_table(
+    table: &Map<String, TomlValue>,
+    location: &str,
+    report: &mut dyn FnMut(&str, String),
+) -> Vec<JsonCondition> {
+    let mut conditions = vec![];
+    for (key, value) in table {
+        let location = format!("{}.{}", location, key);
+        match key.as_str() {
+            "any" | "all" => {
+                let groups = match value.as_array() {
+                    Some(groups) => groups,
+                    None => {
+                        report(&location, String::from("must be an array of tables"));
+                        continue;
+                    }
+                };
+
+                let mut group_conditions = vec![];
+                for (index, group) in groups.iter().enumerate() {
+                    let location = format!("{}[{}]", location, index);
+                    match group.as_table() {
+                        Some(group) => group_conditions.push(JsonCondition::All(
+                            read_condition_table(group, &location, report),
+                        )),
+                        None => report(&location, String::from("must be a table of conditions")),
+                    }
+                }
+
+                conditions.push(match key.as_str() {
+                    "any" => JsonCondition::Any(group_conditions),
+                    _ => JsonCondition::All(group_conditions),
+                });
+            }
+            path => {
+                let path = match JsonPath::parse(path) {
+                    Ok(path) => path,
+                    Err(error) => {
+                        report(&location, format!("is not a valid JSON path ({})", error));
+                        continue;
+                    }
+                };
+                match read_test(value) {
+                    Ok(test) => conditions.push(JsonCondition::Value { path, test }),
+                    Err(message) => report(&location, message),
+                }
+            }
+        }
+    }
+
+    conditions
+}
+
+fn read_test(value: &TomlValue) -> Result<ValueTest, String> {
+    let table = match value {
+        TomlValue::Table(table) => table,
+        value => return Ok(ValueTest::Equals(json!(value))),
+    };
+
+    let operators: Vec<&str> = table
+        .keys()
+        .map(String::as_str)
+        .filter(|key| *key != "format")
+        .collect();
+    let operator = match operators.as_slice() {
+        [operator] => *operator,
+        _ => {
+            return Err(format!(
+                "must have exactly one of {}",
+                TEST_OPERATORS.join(", ")
+            ))
+        }
+    };
+    if table.contains_key("format") && !matches!(operator, "before" | "after") {
+        return Err(String::from(
+            "format can only be used with before and after",
+        ));
+    }
+
+    let operand = &table[operator];
+    let flag = || {
+        operand
+            .as_bool()
+            .ok_or(format!("{} must be a boolean", operator))
+    };
+    let compare = |ordering: Ordering, or_equal: bool| {
+        let operand = match operand {
+            TomlValue::Integer(integer) => *integer as f64,
+            TomlValue::Float(float) => *float,
+            _ => return Err(format!("{} must be a number", operator)),
+        };
+        Ok(ValueTest::Compare {
+            ordering,
+            or_equal,
+            operand,
+        })
+    };
+
+    match operator {
+        "eq" => Ok(ValueTest::Equals(json!(operand))),
+        "ne" => Ok(ValueTest::NotEquals(json!(operand))),
+        "exists" => Ok(match flag()? {
+            true => ValueTest::Exists,
+            false => ValueTest::Missing,
+        }),
+        "missing" => Ok(match flag()? {
+            true => ValueTest::Missing,
+            false => ValueTest::Exists,
+        }),
+        "matches" => {
+            let pattern = operand
+                .as_str()
+                .ok_or(String::from("matches must be a string"))?;
+            Regex::new(pattern)
+                .map(ValueTest::Matches)
+                .map_err(|error| format!("matches is not a valid regex ({})", error))
+        }
+        "lt" => compare(Ordering::Less, false),
+        "le" => compare(Ordering::Less, true),
+        "gt" => compare(Ordering::Greater, false),
+        "ge" => compare(Ordering::Greater, true),
+        "before" | "after" => {
+            let invalid = || format!("{} must be \"now\" or a date with an offset", operator);
+            let moment = match operand {
+                TomlValue::String(now) if now == "now" => DateOperand::Now,
+                TomlValue::String(date) => DateTime::parse_from_rfc3339(date)
+                    .map(|date| DateOperand::At(date.into()))
+                    .map_err(|_| invalid())?,
+                TomlValue::Datetime(date) => DateTime::parse_from_rfc3339(&date.to_string())
+                    .map(|date| DateOperand::At(date.into()))
+                    .map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            };
+            let format = match table.get("format") {
+                Some(TomlValue::String(format)) => Some(JsonDateTimeFormat::parse(format)),
+                Some(_) => return Err(String::from("format must be a string")),
+                None => None,
+            };
+
+            Ok(ValueTest::Date {
+                after: operator == "after",
+                moment,
+                format,
+            })
+        }
+        other => Err(format!(
+            "has an unknown operator {} (expected one of {})",
+            other,
+            TEST_OPERATORS.join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use regex::Regex;
+    use serde_json::json;
+    use toml::Value as TomlValue;
+
+    use crate::{
+        config::{reader::InvalidConfig, settings::get_settings},
+        parsers::{json::keys, json_path::JsonPath},
+        structs::{DateOperand, JsonCondition, JsonDateTimeFormat, ValueTest},
+    };
+
+    #[test]
+    fn test_read_conditions() {
+        let config = r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            preset = "pixiv-comic"
+            work = "8789"
+            [targets.keys.skip]
+            "episode.state" = { ne = "public" }
+            any = [{ "episode.price" = { gt = 0 }, "episode.rental" = { missing = true } }]
+            [targets.keys.include]
+            "episode.publish_start" = { before = "now", format = "unixsec" }
+            "episode.sub_title" = { matches = "^第\\d+話" }
+        "###;
+        let settings = get_settings(&config.parse::<TomlValue>().unwrap()).unwrap();
+
+        let keys = keys(&settings.targets[0].rules).unwrap();
+        let value = |path: &str, test| JsonCondition::Value {
+            path: JsonPath::parse(path).unwrap(),
+            test,
+        };
+        assert_eq!(
+            keys.skip,
+            vec![
+                JsonCondition::Any(vec![JsonCondition::All(vec![
+                    value(
+                        "episode.price",
+                        ValueTest::Compare {
+                            ordering: Ordering::Greater,
+                            or_equal: false,
+                            operand: 0.0,
+                        },
+                    ),
+                    value("episode.rental", ValueTest::Missing),
+                ])]),
+                value("episode.state", ValueTest::NotEquals(json!("public"))),
+                // Merged from the preset
+                value("readable", ValueTest::Equals(json!(false))),
+            ],
+        );
+        assert_eq!(
+            keys.include,
+            vec![
+                value(
+                    "episode.publish_start",
+                    ValueTest::Date {
+                        after: false,
+                        moment: DateOperand::Now,
+                        format: Some(JsonDateTimeFormat::UnixSec),
+                    },
+                ),
+                value(
+                    "episode.sub_title",
+                    ValueTest::Matches(Regex::new(r"^第\d+話").unwrap()),
+                ),
+            ],
+        );
+
+        let config = config
+            .replace("{ ne = \"public\" }", "{ ne = 1, eq = 2 }")
+            .replace("{ gt = 0 }", "{ gt = \"0\" }");
+        let error = get_settings(&config.parse::<TomlValue>().unwrap()).unwrap_err();
+        let errors = &error.downcast_ref::<InvalidConfig>().unwrap().errors;
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].ends_with("keys.skip.any[0].episode.price gt must be a number"));
+        assert!(errors[1].contains("keys.skip.episode.state must have exactly one of"));
+    }
+}
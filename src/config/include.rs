@@ -0,0 +1,321 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use toml::{map::Map, Value as TomlValue};
+
+use crate::parsers::parser::PARSERS;
+
+use super::reader::InvalidConfig;
+
+/// Key added to the targets of included files, holding the file and the index of the target in it,
+/// so their errors can point to where they came from. It can't be written in a settings file.
+pub const INCLUDED_FROM_KEY: &str = "$includedFrom";
+
+/// Reads and parses a TOML file.
+pub fn read_toml_file(path: &Path) -> Result<TomlValue> {
+    let file_contents = fs::read(path)
+        .map_err(|error| anyhow!("Could not read {} ({}).", path.display(), error))?;
+
+    let config: String = String::from_utf8_lossy(&file_contents).into_owned();
+    let config: TomlValue = config
+        .parse()
+        .map_err(|error| anyhow!("Could not parse {} ({}).", path.display(), error))?;
+
+    Ok(config)
+}
+
+/// Lists every `*.toml` file inside the directory set as `includeDir`, sorted by filename.
+/// A relative `includeDir` is resolved from the directory of the main config file.
+/// Returns an empty list if `includeDir` is not set.
+pub fn find_include_files(config_path: &Path, config: &TomlValue) -> Result<Vec<PathBuf>> {
+    let include_dir = match config.get("includeDir") {
+        Some(TomlValue::String(include_dir)) => include_dir,
+        Some(_) => bail!("includeDir must be a string"),
+        None => return Ok(vec![]),
+    };
+
+    let include_dir = match config_path.parent() {
+        Some(parent) => parent.join(include_dir),
+        None => PathBuf::from(include_dir),
+    };
+
+    let entries = fs::read_dir(&include_dir).map_err(|error| {
+        anyhow!(
+            "Could not read include directory {} ({}).",
+            include_dir.display(),
+            error
+        )
+    })?;
+
+    let mut files = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension == "toml")
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Appends the `targets` arrays of the included files to the main config's `targets`.
+/// Target names must be unique across all files; every duplicate is reported with both paths.
+pub fn include_targets(
+    config: &mut TomlValue,
+    config_path: &Path,
+    include_files: &[PathBuf],
+) -> Result<()> {
+    let root = match config.as_table_mut() {
+        Some(root) => root,
+        None => bail!("Config file is not a table."),
+    };
+
+    let mut errors = vec![];
+    let mut origins: HashMap<String, PathBuf> = HashMap::new();
+    let mut register = |target: &TomlValue, path: &Path, errors: &mut Vec<String>| {
+        let name = match target.get("name").and_then(|name| name.as_str()) {
+            Some(name) => name,
+            None => return,
+        };
+
+        match origins.get(name) {
+            Some(origin) => errors.push(format!(
+                "target name \"{}\" is defined more than once (in {} and {})",
+                name,
+                origin.display(),
+                path.display(),
+            )),
+            None => {
+                origins.insert(name.to_owned(), path.to_owned());
+            }
+        }
+    };
+
+    if let Some(TomlValue::Array(targets)) = root.get("targets") {
+        for target in targets {
+            register(target, config_path, &mut errors);
+        }
+    }
+
+    let mut included_targets = vec![];
+    for path in include_files {
+        let included = read_toml_file(path)?;
+        let included = match included.as_table() {
+            Some(included) => included,
+            None => bail!("{} is not a table.", path.display()),
+        };
+
+        for key in included.keys() {
+            if key != "targets" {
+                errors.push(format!(
+                    "{}: {} is not allowed in an included file (only targets is)",
+                    path.display(),
+                    key
+                ));
+            }
+        }
+
+        match included.get("targets") {
+            Some(TomlValue::Array(targets)) => {
+                for (index, target) in targets.iter().enumerate() {
+                    register(target, path, &mut errors);
+
+                    let mut target = target.to_owned();
+                    if let TomlValue::Table(table) = &mut target {
+                        let mut origin = Map::new();
+                        origin.insert(
+                            String::from("file"),
+                            TomlValue::String(path.display().to_string()),
+                        );
+                        origin.insert(String::from("index"), TomlValue::Integer(index as i64));
+                        table.insert(INCLUDED_FROM_KEY.to_owned(), TomlValue::Table(origin));
+                    }
+                    included_targets.push(target);
+                }
+            }
+            Some(_) => errors.push(format!(
+                "{}: targets must be an array of tables",
+                path.display()
+            )),
+            None => (),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(InvalidConfig { errors }.into());
+    }
+
+    if included_targets.is_empty() {
+        return Ok(());
+    }
+
+    // If the main file's `targets` has the wrong type it's left as is for validation to report
+    if let TomlValue::Array(targets) = root
+        .entry("targets")
+        .or_insert_with(|| TomlValue::Array(vec![]))
+    {
+        targets.extend(included_targets);
+    }
+
+    Ok(())
+}
+
+/// Resolves the relative paths in the file keys of every target (like `script.file`)
+/// from the directory of the main config file, the same way as `includeDir`.
+pub fn resolve_target_files(config: &mut TomlValue, config_dir: &Path) {
+    let targets = match config.get_mut("targets") {
+        Some(TomlValue::Array(targets)) => targets,
+        _ => return,
+    };
+
+    for target in targets {
+        for (table, key) in file_keys() {
+            if let Some(TomlValue::String(file)) =
+                target.get_mut(table).and_then(|table| table.get_mut(key))
+            {
+                *file = config_dir.join(&*file).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+/// Lists the files used by the targets of a config file, so they can be watched for changes.
+pub fn find_target_files(config: &TomlValue, config_dir: &Path) -> Vec<PathBuf> {
+    let targets = match config.get("targets") {
+        Some(TomlValue::Array(targets)) => targets,
+        _ => return vec![],
+    };
+
+    let mut files = vec![];
+    for target in targets {
+        for (table, key) in file_keys() {
+            if let Some(file) = target
+                .get(table)
+                .and_then(|table| table.get(key))
+                .and_then(|file| file.as_str())
+            {
+                files.push(config_dir.join(file));
+            }
+        }
+    }
+
+    files
+}
+
+/// The file keys of every parser, whatever the mode of the target (it can come from a preset).
+fn file_keys() -> impl Iterator<Item = (&'static str, &'static str)> {
+    PARSERS
+        .iter()
+        .flat_map(|parser| parser.file_keys())
+        .copied()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::config::{
+        reader::InvalidConfig,
+        settings::{get_config, get_settings},
+    };
+
+    #[test]
+    fn test_include_targets() {
+        let directory = std::env::temp_dir().join(format!("dmt-include-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("targets.d")).unwrap();
+
+        let settings_path = directory.join("settings.toml");
+        fs::write(
+            &settings_path,
+            r###"
+            token = ""
+            includeDir = "targets.d"
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            "###,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("targets.d/b.toml"),
+            r###"
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "rss"
+            "###,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("targets.d/a.toml"),
+            r###"
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/rss"
+            mode = "rss"
+            "###,
+        )
+        .unwrap();
+        fs::write(directory.join("targets.d/notes.txt"), "not a target").unwrap();
+
+        let config = get_config(Some(settings_path.to_str().unwrap())).unwrap();
+        let names: Vec<&str> = config["targets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|target| target["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Shounen wo Kau", "Bokuyaba", "Kusunoki Debut"]);
+        assert_eq!(get_settings(&config).unwrap().targets.len(), 3);
+
+        // Errors of included targets name their file and their index in it
+        fs::write(
+            directory.join("targets.d/b.toml"),
+            r###"
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "rss"
+            delay = -1
+            "###,
+        )
+        .unwrap();
+        let config = get_config(Some(settings_path.to_str().unwrap())).unwrap();
+        let error = get_settings(&config).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+        assert_eq!(error.errors.len(), 1);
+        assert!(error.errors[0]
+            .ends_with("b.toml: targets[0] \"Kusunoki Debut\": delay must be between 0 and 255"));
+
+        // Duplicate names are rejected, naming both files
+        fs::write(
+            directory.join("targets.d/c.toml"),
+            r###"
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/rss"
+            mode = "rss"
+            "###,
+        )
+        .unwrap();
+        let error = get_config(Some(settings_path.to_str().unwrap())).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+        assert_eq!(error.errors.len(), 1);
+        assert!(error.errors[0].contains("a.toml"));
+        assert!(error.errors[0].contains("c.toml"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
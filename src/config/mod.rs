@@ -0,0 +1,9 @@
+pub mod conditions;
+pub mod include;
+pub mod presets;
+pub mod reader;
+pub mod reload;
+pub mod schema;
+pub mod secrets;
+pub mod settings;
+pub mod transforms;
@@ -0,0 +1,261 @@
+use toml::{map::Map, Value as TomlValue};
+use url::Url;
+
+use super::reader::TableReader;
+
+/// GigaViewer-based sites (comic-zenon, shonenjumpplus, etc.) publish an RSS feed per series.
+/// `source` has to be set to the feed URL since the host differs between sites.
+const GIGAVIEWER: &str = r###"
+mode = "rss"
+ascendingSource = false
+"###;
+
+/// The pixiv comic episodes API. `work` is the numeric work ID in `comic.pixiv.net/works/<work>`.
+const PIXIV_COMIC: &str = r###"
+mode = "json"
+source = "https://comic.pixiv.net/api/app/works/{work}/episodes?page=1&order=desc"
+ascendingSource = false
+baseUrl = "https://comic.pixiv.net"
+[requestHeaders]
+X-Requested-With = "pixivcomic"
+Referer = "https://comic.pixiv.net/works/{work}"
+[keys]
+chapters = "data.episodes"
+number = "episode.numbering_title"
+title = ["episode.numbering_title", "episode.sub_title"]
+date = "episode.read_start_at"
+dateFormat = "unix"
+url = "episode.viewer_path"
+[keys.skip]
+readable = false
+"###;
+
+/// The `__NEXT_DATA__` JSON embedded in comic-fuz manga pages.
+/// `work` is the numeric manga ID in `comic-fuz.com/manga/<work>`.
+const COMIC_FUZ: &str = r###"
+mode = "json_in_html"
+source = "https://comic-fuz.com/manga/{work}"
+ascendingSource = false
+baseUrl = "https://comic-fuz.com/manga/viewer/"
+timezone = "Asia/Tokyo"
+[tags]
+chaptersTag = "script#__NEXT_DATA__"
+[keys]
+chapters = "props.pageProps.chapters[*].chapters"
+number = "chapterId"
+title = "chapterMainName"
+date = "updatedDate"
+dateFormat = "%Y/%m/%d"
+url = "chapterId"
+"###;
+
+/// Names of the built-in presets and their definitions.
+pub const PRESETS: [(&str, &str); 3] = [
+    ("gigaviewer", GIGAVIEWER),
+    ("pixiv-comic", PIXIV_COMIC),
+    ("comic-fuz", COMIC_FUZ),
+];
+
+/// Expands the `preset` of a target into a full target table.
+/// Values set explicitly in the target take precedence over the preset's, table by table.
+/// Returns `None` if the preset could not be applied.
+pub fn apply_preset(
+    reader: &TableReader,
+    target: &Map<String, TomlValue>,
+) -> Option<Map<String, TomlValue>> {
+    let preset_name = reader.string("preset")?;
+
+    let preset = match PRESETS.iter().find(|(name, _)| *name == preset_name) {
+        Some((_, preset)) => preset,
+        None => {
+            let names: Vec<String> = PRESETS
+                .iter()
+                .map(|(name, _)| format!("\"{}\"", name))
+                .collect();
+            reader.error(
+                "preset",
+                format!(
+                    "must be one of {} (found \"{}\")",
+                    names.join(", "),
+                    preset_name
+                ),
+            );
+            return None;
+        }
+    };
+
+    let work = reader.string("work");
+    let mut preset: TomlValue = preset.parse().expect("Built-in preset is not valid TOML.");
+    if preset.to_string().contains("{work}") {
+        let work = match work {
+            Some(work) => work,
+            None => {
+                if !reader.has("work") {
+                    reader.error(
+                        "work",
+                        format!("is required for preset \"{}\"", preset_name),
+                    );
+                }
+                return None;
+            }
+        };
+        replace_placeholder(&mut preset, "{work}", &get_work_id(&work));
+    } else if reader.has("work") {
+        reader.error("work", format!("is not used by preset \"{}\"", preset_name));
+        return None;
+    }
+
+    let mut expanded = match preset {
+        TomlValue::Table(table) => table,
+        _ => unreachable!(),
+    };
+    merge_tables(&mut expanded, target);
+
+    Some(expanded)
+}
+
+/// Gets the work ID out of a work page URL (its last path segment),
+/// or returns the value as is if it's not a URL.
+fn get_work_id(work: &str) -> String {
+    match Url::parse(work) {
+        Ok(url) => url
+            .path_segments()
+            .and_then(|segments| segments.rev().find(|segment| !segment.is_empty()))
+            .unwrap_or(work)
+            .to_owned(),
+        Err(_) => work.to_owned(),
+    }
+}
+
+/// Replaces a placeholder inside every string of a TOML value.
+fn replace_placeholder(value: &mut TomlValue, placeholder: &str, replacement: &str) {
+    match value {
+        TomlValue::String(string) => *string = string.replace(placeholder, replacement),
+        TomlValue::Array(array) => {
+            for item in array {
+                replace_placeholder(item, placeholder, replacement);
+            }
+        }
+        TomlValue::Table(table) => {
+            for (_, item) in table.iter_mut() {
+                replace_placeholder(item, placeholder, replacement);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Merges `overrides` into `base`. Tables present in both are merged recursively,
+/// any other value in `overrides` replaces the one in `base`.
+fn merge_tables(base: &mut Map<String, TomlValue>, overrides: &Map<String, TomlValue>) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(TomlValue::Table(base_table)), TomlValue::Table(override_table)) => {
+                merge_tables(base_table, override_table)
+            }
+            _ => {
+                base.insert(key.to_owned(), value.to_owned());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use toml::Value as TomlValue;
+
+    use crate::{
+        config::settings::get_settings,
+        parsers::{json::keys, json_in_html::tags_and_keys, json_path::JsonPath},
+        structs::{JsonCondition, JsonDateTimeFormat, ValueTest},
+    };
+
+    #[test]
+    fn test_apply_preset() {
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            preset = "pixiv-comic"
+            work = "8789"
+            delay = 1
+            [targets.keys]
+            dateFormat = "unixsec"
+
+            [[targets]]
+            name = "Idol x Idol Story"
+            preset = "comic-fuz"
+            work = "https://comic-fuz.com/manga/3140"
+        "###
+        .parse()
+        .unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        let pixiv = &settings.targets[0];
+        assert_eq!(pixiv.mode.name(), "json");
+        assert_eq!(
+            pixiv.source,
+            "https://comic.pixiv.net/api/app/works/8789/episodes?page=1&order=desc"
+        );
+        assert_eq!(
+            pixiv.request_headers.as_ref().unwrap()["Referer"],
+            "https://comic.pixiv.net/works/8789"
+        );
+        assert_eq!(pixiv.delay, Some(1));
+        let keys = keys(&pixiv.rules).unwrap();
+        // Overridden by the target
+        assert_eq!(keys.date_format, Some(JsonDateTimeFormat::UnixSec));
+        // Kept from the preset
+        assert_eq!(keys.chapters.to_string(), "data.episodes");
+        assert_eq!(
+            keys.skip,
+            vec![JsonCondition::Value {
+                path: JsonPath::key("readable"),
+                test: ValueTest::Equals(json!(false)),
+            }],
+        );
+
+        let fuz = &settings.targets[1];
+        assert_eq!(fuz.mode.name(), "json_in_html");
+        assert_eq!(fuz.source, "https://comic-fuz.com/manga/3140");
+        assert_eq!(
+            tags_and_keys(&fuz.rules).unwrap().tags.chapters_tag,
+            "script#__NEXT_DATA__"
+        );
+    }
+
+    #[test]
+    fn test_apply_preset_errors() {
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            preset = "pixiv-comic"
+
+            [[targets]]
+            name = "Idol x Idol Story"
+            preset = "comic-fuzz"
+            work = "3140"
+
+            [[targets]]
+            name = "Kaiju No. 8"
+            preset = "gigaviewer"
+            source = "https://shonenjumpplus.com/rss/series/3269754496638370192"
+            work = "3269754496638370192"
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err().to_string();
+
+        assert!(error.contains(
+            "targets[0] \"Kusunoki Debut\": work is required for preset \"pixiv-comic\""
+        ));
+        assert!(error.contains("targets[1] \"Idol x Idol Story\": preset must be one of"));
+        assert!(
+            error.contains("targets[2] \"Kaiju No. 8\": work is not used by preset \"gigaviewer\"")
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Display};
+
+use toml::{map::Map, Value as TomlValue};
+
+/// List of every problem found while reading the configuration.
+/// It is shared between readers so that nested tables report into the same list.
+pub type ErrorList = RefCell<Vec<String>>;
+
+/// Error returned when the configuration has one or more invalid values.
+/// Every problem is listed at once instead of stopping at the first one.
+#[derive(Debug)]
+pub struct InvalidConfig {
+    pub errors: Vec<String>,
+}
+
+impl Display for InvalidConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Invalid configuration ({} problems):", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidConfig {}
+
+/// Turns the collected errors into a `Result`, failing if there is at least one.
+pub fn into_result(errors: ErrorList) -> Result<(), InvalidConfig> {
+    let errors = errors.into_inner();
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    Err(InvalidConfig { errors })
+}
+
+/// Reads typed values out of a TOML table.
+/// Type mismatches are recorded into the error list with the location of the key
+/// (e.g. `targets[3] "Bokuyaba": tags.dateFormat must be a string`) and read as `None`.
+pub struct TableReader<'a> {
+    table: &'a Map<String, TomlValue>,
+    context: String,
+    prefix: String,
+    errors: &'a ErrorList,
+}
+
+impl<'a> TableReader<'a> {
+    pub fn new(table: &'a Map<String, TomlValue>, context: &str, errors: &'a ErrorList) -> Self {
+        Self {
+            table,
+            context: context.to_owned(),
+            prefix: String::new(),
+            errors,
+        }
+    }
+
+    /// Full path of a key inside this table, used in error messages.
+    pub fn path(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// Records a problem about a key of this table.
+    pub fn error(&self, key: &str, message: impl Display) {
+        let location = match self.context.is_empty() {
+            true => self.path(key),
+            false => format!("{}: {}", self.context, self.path(key)),
+        };
+        self.errors
+            .borrow_mut()
+            .push(format!("{} {}", location, message));
+    }
+
+    pub fn errors(&self) -> &'a ErrorList {
+        self.errors
+    }
+
+    pub fn get(&self, key: &str) -> Option<&'a TomlValue> {
+        self.table.get(key)
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.table.contains_key(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &'a String> {
+        self.table.keys()
+    }
+
+    /// Reads an optional string.
+    pub fn string(&self, key: &str) -> Option<String> {
+        match self.table.get(key)? {
+            TomlValue::String(string) => Some(string.to_owned()),
+            _ => {
+                self.error(key, "must be a string");
+                None
+            }
+        }
+    }
+
+    /// Reads an optional string, treating an empty string as if the key was not set.
+    pub fn non_empty_string(&self, key: &str) -> Option<String> {
+        self.string(key).filter(|string| !string.is_empty())
+    }
+
+    /// Reads a string that must be present.
+    pub fn required_string(&self, key: &str) -> Option<String> {
+        if !self.has(key) {
+            self.error(key, "is required");
+            return None;
+        }
+
+        self.string(key)
+    }
+
+    /// Reads an optional boolean.
+    pub fn bool(&self, key: &str) -> Option<bool> {
+        match self.table.get(key)? {
+            TomlValue::Boolean(boolean) => Some(*boolean),
+            _ => {
+                self.error(key, "must be a boolean");
+                None
+            }
+        }
+    }
+
+    /// Reads an optional integer that must fit between `min` and `max` (inclusive).
+    pub fn integer(&self, key: &str, min: i64, max: i64) -> Option<i64> {
+        match self.table.get(key)? {
+            TomlValue::Integer(integer) if (min..=max).contains(integer) => Some(*integer),
+            TomlValue::Integer(_) => {
+                self.error(key, format!("must be between {} and {}", min, max));
+                None
+            }
+            _ => {
+                self.error(key, "must be an integer");
+                None
+            }
+        }
+    }
+
+    /// Reads a string or an array of strings that must be present.
+    /// A single string is wrapped into a vector. Empty strings are dropped.
+    pub fn required_strings(&self, key: &str) -> Option<Vec<String>> {
+        let value = match self.table.get(key) {
+            Some(value) => value,
+            None => {
+                self.error(key, "is required");
+                return None;
+            }
+        };
+
+        let invalid = || {
+            self.error(key, "must be a string or an array of strings");
+            None
+        };
+
+        let strings = match value {
+            TomlValue::String(string) => vec![string.to_owned()],
+            TomlValue::Array(array) => {
+                let mut strings = vec![];
+                for item in array {
+                    match item.as_str() {
+                        Some(string) => strings.push(string.to_owned()),
+                        None => return invalid(),
+                    }
+                }
+                strings
+            }
+            _ => return invalid(),
+        };
+
+        Some(strings.into_iter().filter(|s| !s.is_empty()).collect())
+    }
+
+    /// Reads an optional table of strings, e.g. the named `fields` of the JSON keys.
+    pub fn string_table(&self, key: &str) -> Option<HashMap<String, String>> {
+        let table = match self.has(key) {
+            true => self.table(key)?,
+            false => return Some(HashMap::new()),
+        };
+
+        let mut strings = HashMap::new();
+        let mut valid = true;
+        for name in table.keys() {
+            match table.string(name) {
+                Some(string) => {
+                    strings.insert(name.to_owned(), string);
+                }
+                None => valid = false,
+            }
+        }
+
+        valid.then_some(strings)
+    }
+
+    /// Gets a reader for a nested table.
+    pub fn table(&self, key: &str) -> Option<TableReader<'a>> {
+        match self.table.get(key)? {
+            TomlValue::Table(table) => Some(TableReader {
+                table,
+                context: self.context.clone(),
+                prefix: format!("{}{}.", self.prefix, key),
+                errors: self.errors,
+            }),
+            _ => {
+                self.error(key, "must be a table");
+                None
+            }
+        }
+    }
+
+    /// Records an error for every key that is not in the list of known keys.
+    /// Suggests the camelCase spelling when a key was written in snake_case.
+    pub fn deny_unknown_keys(&self, known: &[&str]) {
+        for key in self.table.keys() {
+            if known.contains(&key.as_str()) {
+                continue;
+            }
+
+            let camel_case = to_camel_case(key);
+            match known.contains(&camel_case.as_str()) {
+                true => self.error(
+                    key,
+                    format!("is not a known key (did you mean {}?)", camel_case),
+                ),
+                false => self.error(key, "is not a known key"),
+            }
+        }
+    }
+}
+
+fn to_camel_case(key: &str) -> String {
+    let mut camel_case = String::new();
+    let mut uppercase_next = false;
+    for character in key.chars() {
+        if character == '_' {
+            uppercase_next = true;
+        } else if uppercase_next {
+            camel_case.extend(character.to_uppercase());
+            uppercase_next = false;
+        } else {
+            camel_case.push(character);
+        }
+    }
+    camel_case
+}
@@ -0,0 +1,138 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+use colored::Colorize;
+use crossbeam::channel::Sender;
+use tokio::time::{interval, Duration};
+
+use crate::{log, structs::Target, CoreMessage};
+
+use super::settings::get_config_files;
+
+/// How often the config file is checked for modifications.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Names of the targets that differ between two versions of the config.
+#[derive(Debug, Default, PartialEq)]
+pub struct TargetChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl TargetChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two lists of targets by their names.
+pub fn diff_targets(old: &[Target], new: &[Target]) -> TargetChanges {
+    let mut changes = TargetChanges::default();
+
+    for new_target in new {
+        match old.iter().find(|target| target.name == new_target.name) {
+            Some(old_target) if old_target != new_target => {
+                changes.changed.push(new_target.name.to_owned())
+            }
+            Some(_) => (),
+            None => changes.added.push(new_target.name.to_owned()),
+        }
+    }
+
+    for old_target in old {
+        if !new.iter().any(|target| target.name == old_target.name) {
+            changes.removed.push(old_target.name.to_owned());
+        }
+    }
+
+    changes
+}
+
+/// Modification times of the main config file and every included file.
+/// Adding or removing an included file also changes the result.
+fn get_config_fingerprint(filename: &str) -> Option<Vec<(PathBuf, SystemTime)>> {
+    let files = get_config_files(filename).ok()?;
+
+    let mut fingerprint = vec![];
+    for path in files {
+        let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+        fingerprint.push((path, modified));
+    }
+
+    Some(fingerprint)
+}
+
+/// Periodically checks the modification times of the config files,
+/// asking core control to reload the config whenever one of them changes.
+pub async fn watch_config_files(filename: String, sender: Sender<CoreMessage>) {
+    let mut last_fingerprint = get_config_fingerprint(&filename);
+    let mut ticker = interval(WATCH_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let fingerprint = get_config_fingerprint(&filename);
+        if fingerprint.is_none() || fingerprint == last_fingerprint {
+            continue;
+        }
+        last_fingerprint = fingerprint;
+
+        log!("{} Config files have been modified.", "[CORE]".blue());
+        if sender.send(CoreMessage::ReloadConfig).is_err() {
+            break;
+        }
+    }
+}
+
+/// Asks core control to reload the config every time the process receives SIGHUP.
+#[cfg(unix)]
+pub async fn listen_for_hangup(sender: Sender<CoreMessage>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(error) => {
+            log!(
+                "{} Could not listen for SIGHUP: {}.",
+                "[CORE]".blue(),
+                error
+            );
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        log!("{} SIGHUP received.", "[CORE]".blue());
+        if sender.send(CoreMessage::ReloadConfig).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::structs::Target;
+
+    use super::diff_targets;
+
+    #[test]
+    fn test_diff_targets() {
+        let old = vec![
+            Target::for_test("Kept", "https://comic-rss.com/kept.rss"),
+            Target::for_test("Changed", "https://comic-rss.com/old.rss"),
+            Target::for_test("Removed", "https://comic-rss.com/removed.rss"),
+        ];
+        let new = vec![
+            Target::for_test("Kept", "https://comic-rss.com/kept.rss"),
+            Target::for_test("Changed", "https://comic-rss.com/new.rss"),
+            Target::for_test("Added", "https://comic-rss.com/added.rss"),
+        ];
+
+        let changes = diff_targets(&old, &new);
+
+        assert_eq!(changes.added, vec!["Added"]);
+        assert_eq!(changes.removed, vec!["Removed"]);
+        assert_eq!(changes.changed, vec!["Changed"]);
+        assert!(diff_targets(&new, &new).is_empty());
+    }
+}
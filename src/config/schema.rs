@@ -0,0 +1,429 @@
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::parsers::parser::PARSERS;
+
+use super::{
+    conditions::TEST_OPERATORS,
+    presets::PRESETS,
+    transforms::{SIMPLE_STEPS, TRANSFORM_FIELDS},
+};
+
+/// Builds a JSON Schema (draft-07) describing the whole settings file,
+/// so that editors can autocomplete and validate it.
+pub fn get_config_schema() -> JsonValue {
+    let mut definitions = json!({
+        "secret": secret_schema("A plain string, an environment variable or a file."),
+        "target": target_schema(),
+        "pagination": pagination_schema(),
+        "detail": detail_schema(),
+        "transforms": transforms_schema(),
+        "conditions": conditions_schema(),
+    });
+    // Each parser describes its own tables
+    for parser in PARSERS {
+        for table in parser.tables() {
+            definitions[*table] = parser.table_schema(table);
+        }
+    }
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "decatholac-mango-tarnished settings",
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["token", "targets"],
+        "properties": {
+            "token": secret_schema("Discord bot token."),
+            "cron": {
+                "type": "string",
+                "description": "Cron expression (with seconds) of the fetch schedule. Defaults to \"0 0 1 * * *\".",
+            },
+            "includeDir": {
+                "type": "string",
+                "description": "Directory whose *.toml files add more [[targets]], relative to this file.",
+            },
+            "targets": {
+                "type": "array",
+                "items": { "$ref": "#/definitions/target" },
+            },
+        },
+        "definitions": definitions,
+    })
+}
+
+/// A value that can also be read from an environment variable or a file.
+fn secret_schema(description: &str) -> JsonValue {
+    json!({
+        "description": description,
+        "oneOf": [
+            { "type": "string" },
+            {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["env"],
+                "properties": { "env": { "type": "string" } },
+            },
+            {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["file"],
+                "properties": { "file": { "type": "string" } },
+            },
+        ],
+    })
+}
+
+fn target_schema() -> JsonValue {
+    let presets: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+    let modes: Vec<&str> = PARSERS.iter().map(|parser| parser.name()).collect();
+    let server_ids = json!({
+        "type": "array",
+        "items": { "type": ["string", "integer"], "pattern": "^[0-9]+$" },
+    });
+
+    // Targets without a preset have to define everything themselves
+    let mut rules = vec![
+        json!({
+            "if": { "not": { "required": ["preset"] } },
+            "then": { "required": ["source", "mode"] },
+        }),
+        // The title keys can only be left out when the title comes from the template
+        json!({
+            "if": { "not": { "required": ["titleTemplate"] } },
+            "then": {
+                "properties": {
+                    "keys": { "required": ["title"] },
+                    "paths": { "required": ["title"] },
+                },
+            },
+        }),
+    ];
+    for parser in PARSERS {
+        let required = parser.required_tables();
+        if required.is_empty() {
+            continue;
+        }
+        rules.push(json!({
+            "if": {
+                "required": ["mode"],
+                "properties": { "mode": { "const": parser.name() } },
+                "not": { "required": ["preset"] },
+            },
+            "then": { "required": required },
+        }));
+    }
+
+    let mut schema = json!({
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["name"],
+        "properties": {
+            "name": { "type": "string", "description": "Name of the manga, shown in announcements." },
+            "preset": { "enum": presets, "description": "Built-in target definition to start from." },
+            "work": { "type": "string", "description": "Work ID or URL used by the preset." },
+            "source": { "type": "string", "format": "uri" },
+            "ascendingSource": {
+                "type": "boolean",
+                "description": "Whether the source lists old chapters first.",
+            },
+            "mode": { "enum": modes },
+            "baseUrl": { "type": "string", "description": "Prepended to relative chapter URLs." },
+            "requestHeaders": {
+                "type": "object",
+                "additionalProperties": { "$ref": "#/definitions/secret" },
+            },
+            "delay": {
+                "type": "integer",
+                "minimum": 0,
+                "maximum": 255,
+                "description": "Days to delay the announcement of new chapters.",
+            },
+            "timezone": {
+                "type": "string",
+                "description": "IANA time zone (e.g. Asia/Tokyo) of the dates written without an offset. Defaults to UTC.",
+            },
+            "cron": {
+                "type": "string",
+                "description": "Fetch schedule for this target, overriding the global cron.",
+            },
+            "enabled": { "type": "boolean", "default": true },
+            "servers": server_ids.clone(),
+            "excludeServers": server_ids,
+            "pagination": { "$ref": "#/definitions/pagination" },
+            "detail": { "$ref": "#/definitions/detail" },
+            "transforms": { "$ref": "#/definitions/transforms" },
+            "titleTemplate": {
+                "type": "string",
+                "description": "Chapter title format, e.g. \"{number}[: {subtitle}]\". Sections in [] are left out when a field in them is empty.",
+            },
+        },
+        "allOf": rules,
+    });
+    for parser in PARSERS {
+        for table in parser.tables() {
+            schema["properties"][*table] = json!({ "$ref": format!("#/definitions/{}", table) });
+        }
+    }
+
+    schema
+}
+
+fn conditions_schema() -> JsonValue {
+    let mut operators = Map::new();
+    for operator in TEST_OPERATORS {
+        let operand = match operator {
+            "eq" | "ne" => json!({}),
+            "exists" | "missing" => json!({ "type": "boolean" }),
+            "matches" => json!({ "type": "string" }),
+            "before" | "after" => json!({
+                "type": "string",
+                "description": "\"now\" or a RFC 3339 date.",
+            }),
+            _ => json!({ "type": "number" }),
+        };
+        operators.insert(operator.to_owned(), operand);
+    }
+    operators.insert(
+        String::from("format"),
+        json!({
+            "type": "string",
+            "description": "dateFormat of the value for before and after (the dateFormat of the keys by default).",
+        }),
+    );
+
+    let groups = json!({
+        "type": "array",
+        "items": { "$ref": "#/definitions/conditions" },
+    });
+    json!({
+        "type": "object",
+        "description": "Conditions keyed by the JSON path of the tested value. A plain value has to be equal, a table runs a test like { ne = \"public\" }. any and all group tables of conditions.",
+        "properties": {
+            "any": groups.clone(),
+            "all": groups,
+        },
+        "additionalProperties": {
+            "anyOf": [
+                { "type": ["string", "number", "boolean", "array"] },
+                {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "minProperties": 1,
+                    "properties": operators,
+                },
+            ],
+        },
+    })
+}
+
+fn pagination_schema() -> JsonValue {
+    json!({
+        "type": "object",
+        "description": "How to fetch the following pages of a paginated source. Set exactly one of pageParameter, nextTag or cursor.",
+        "additionalProperties": false,
+        "oneOf": [
+            { "required": ["pageParameter"] },
+            { "required": ["nextTag"] },
+            { "required": ["cursor"] },
+        ],
+        "properties": {
+            "pageParameter": {
+                "type": "string",
+                "description": "Query parameter of the source set to the page number.",
+            },
+            "startPage": { "type": "integer", "minimum": 0, "default": 1 },
+            "nextTag": {
+                "type": "string",
+                "description": "CSS selector of the link to the next page (html and json_in_html modes).",
+            },
+            "nextAttribute": { "type": "string", "default": "href" },
+            "cursor": {
+                "type": "string",
+                "description": "Path of the next page's cursor in the JSON response (json mode).",
+            },
+            "cursorParameter": {
+                "type": "string",
+                "description": "Query parameter of the source set to the cursor. Without it, the cursor is the next page's URL.",
+            },
+            "maxPages": { "type": "integer", "minimum": 1, "default": 10 },
+            "stopOnKnown": {
+                "type": "boolean",
+                "default": false,
+                "description": "Stop at the first page that only has chapters saved already.",
+            },
+        },
+    })
+}
+
+fn detail_schema() -> JsonValue {
+    let value = json!({
+        "description": "A tag or { tag, attribute } in html mode, a JSON path in json mode.",
+        "oneOf": [
+            { "type": "string" },
+            {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "tag": { "type": "string" },
+                    "attribute": { "type": "string" },
+                },
+            },
+        ],
+    });
+
+    json!({
+        "type": "object",
+        "description": "Values read from the page of each new chapter, overriding the ones from the chapter list.",
+        "additionalProperties": false,
+        "anyOf": [
+            { "required": ["title"] },
+            { "required": ["date"] },
+            { "required": ["thumbnail"] },
+        ],
+        "properties": {
+            "mode": { "enum": ["html", "json"], "default": "html" },
+            "limit": {
+                "type": "integer",
+                "minimum": 1,
+                "default": 10,
+                "description": "Most chapter pages fetched in a run. Later chapters are left for the next run.",
+            },
+            "title": value.clone(),
+            "date": value.clone(),
+            "dateFormat": {
+                "type": "string",
+                "description": "A strftime format or \"relative\" in html mode, or any dateFormat of the JSON keys in json mode.",
+            },
+            "thumbnail": value,
+        },
+    })
+}
+
+fn transforms_schema() -> JsonValue {
+    let single = |key: &str, description: &str| {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": [key],
+            "properties": { key: { "type": "string", "description": description } },
+        })
+    };
+    let step = json!({
+        "oneOf": [
+            { "enum": SIMPLE_STEPS },
+            {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["from"],
+                "properties": {
+                    "from": {
+                        "enum": TRANSFORM_FIELDS,
+                        "description": "Start over from the extracted value of another field.",
+                    },
+                },
+            },
+            single("regex", "Keep the first capture group of the match."),
+            {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["replace"],
+                "properties": {
+                    "replace": { "type": "string", "description": "Regex of the text to replace." },
+                    "with": { "type": "string", "description": "Replacement, can use $1." },
+                },
+            },
+            single("prefix", "Text added before the value."),
+            single("suffix", "Text added after the value."),
+        ],
+    });
+
+    let mut properties = Map::new();
+    for field in TRANSFORM_FIELDS {
+        properties.insert(
+            field.to_owned(),
+            json!({ "type": "array", "items": step.clone() }),
+        );
+    }
+
+    json!({
+        "type": "object",
+        "description": "Post-processing steps for the extracted chapter fields, applied in order.",
+        "additionalProperties": false,
+        "properties": properties,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value as JsonValue;
+
+    use crate::{
+        config::settings::{
+            parser_tables, ROOT_KEYS, TARGET_DETAIL_KEYS, TARGET_KEYS, TARGET_PAGINATION_KEYS,
+        },
+        parsers::{
+            json::TARGET_KEYS_KEYS, rss::TARGET_FEED_KEYS, script::TARGET_SCRIPT_KEYS,
+            xml::TARGET_PATHS_KEYS,
+        },
+    };
+
+    use super::get_config_schema;
+
+    fn property_names(schema: &JsonValue) -> Vec<&str> {
+        let mut names: Vec<&str> = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn sorted(keys: &[&'static str]) -> Vec<&'static str> {
+        let mut keys = keys.to_vec();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_schema_matches_known_keys() {
+        let schema = get_config_schema();
+
+        assert_eq!(property_names(&schema), sorted(&ROOT_KEYS));
+        let definitions = &schema["definitions"];
+        assert_eq!(
+            property_names(&definitions["target"]),
+            sorted(&[TARGET_KEYS.as_slice(), &parser_tables()].concat())
+        );
+        assert_eq!(
+            property_names(&definitions["keys"]),
+            sorted(&TARGET_KEYS_KEYS)
+        );
+        assert_eq!(
+            property_names(&definitions["feed"]),
+            sorted(&TARGET_FEED_KEYS)
+        );
+        assert_eq!(
+            property_names(&definitions["pagination"]),
+            sorted(&TARGET_PAGINATION_KEYS)
+        );
+        assert_eq!(
+            property_names(&definitions["detail"]),
+            sorted(&TARGET_DETAIL_KEYS)
+        );
+        assert_eq!(
+            property_names(&definitions["paths"]),
+            sorted(&TARGET_PATHS_KEYS)
+        );
+        assert_eq!(
+            property_names(&definitions["script"]),
+            sorted(&TARGET_SCRIPT_KEYS)
+        );
+
+        // The published schema is kept up to date
+        let published: JsonValue =
+            serde_json::from_str(include_str!("../../settings.schema.json")).unwrap();
+        assert_eq!(published, schema);
+    }
+}
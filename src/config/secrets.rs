@@ -0,0 +1,174 @@
+use std::{collections::HashMap, env, fs};
+
+use toml::Value as TomlValue;
+
+use super::reader::TableReader;
+
+/// Reads a value that may be kept out of the settings file. Accepts either
+/// - a plain string (`token = "..."`),
+/// - an environment variable (`token = { env = "DISCORD_TOKEN" }`), or
+/// - a file, read without its surrounding whitespace (`token = { file = "/run/secrets/token" }`).
+pub fn read_secret(reader: &TableReader, key: &str) -> Option<String> {
+    let table = match reader.get(key)? {
+        TomlValue::String(string) => return Some(string.to_owned()),
+        TomlValue::Table(table) => table,
+        _ => {
+            reader.error(
+                key,
+                "must be a string, { env = \"VARIABLE\" } or { file = \"path\" }",
+            );
+            return None;
+        }
+    };
+
+    let source = table.keys().map(String::as_str).collect::<Vec<&str>>();
+    match source.as_slice() {
+        ["env"] => {
+            let name = reader.table(key)?.string("env")?;
+            match env::var(&name) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    reader.error(
+                        &format!("{}.env", key),
+                        format!("refers to environment variable {} which is not set", name),
+                    );
+                    None
+                }
+            }
+        }
+        ["file"] => {
+            let path = reader.table(key)?.string("file")?;
+            match fs::read_to_string(&path) {
+                Ok(value) => Some(value.trim().to_owned()),
+                Err(error) => {
+                    reader.error(
+                        &format!("{}.file", key),
+                        format!("could not be read from {} ({})", path, error),
+                    );
+                    None
+                }
+            }
+        }
+        _ => {
+            reader.error(key, "must have either an env or a file key, but not both");
+            None
+        }
+    }
+}
+
+/// Reads a table of secrets (see `read_secret`), replacing every `${VARIABLE}`
+/// in the values with the value of that environment variable.
+pub fn read_secret_map(reader: &TableReader, key: &str) -> Option<HashMap<String, String>> {
+    let table = reader.table(key)?;
+
+    let mut hashmap = HashMap::new();
+    let mut valid = true;
+    for name in table.keys() {
+        let value = read_secret(&table, name).and_then(|value| {
+            interpolate_env(&value)
+                .map_err(|message| table.error(name, message))
+                .ok()
+        });
+
+        match value {
+            Some(value) => {
+                hashmap.insert(name.to_owned(), value);
+            }
+            None => valid = false,
+        }
+    }
+
+    valid.then_some(hashmap)
+}
+
+/// Replaces every `${VARIABLE}` in a string with the value of that environment variable.
+/// The error message names the variable that is not set.
+pub fn interpolate_env(value: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+
+        let after = &rest[start + 2..];
+        let end = match after.find('}') {
+            Some(end) => end,
+            None => return Err(String::from("has a ${ without a closing }")),
+        };
+
+        let name = &after[..end];
+        match env::var(name) {
+            Ok(variable) => result.push_str(&variable),
+            Err(_) => {
+                return Err(format!(
+                    "refers to environment variable {} which is not set",
+                    name
+                ))
+            }
+        }
+
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+
+    use toml::Value as TomlValue;
+
+    use crate::config::settings::get_settings;
+
+    #[test]
+    fn test_read_secrets() {
+        let token_path = env::temp_dir().join(format!("dmt-token-{}", std::process::id()));
+        fs::write(&token_path, "file-token\n").unwrap();
+        env::set_var("DMT_TEST_SESSION", "abc123");
+
+        let config = format!(
+            r###"
+            token = {{ file = "{}" }}
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "rss"
+            [targets.requestHeaders]
+            Cookie = "PHPSESSID=${{DMT_TEST_SESSION}}; lang=ja"
+            "###,
+            token_path.display()
+        );
+        let config: TomlValue = config.parse().unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        assert_eq!(settings.token, "file-token");
+        assert_eq!(
+            settings.targets[0].request_headers.as_ref().unwrap()["Cookie"],
+            "PHPSESSID=abc123; lang=ja"
+        );
+
+        let config: TomlValue = r###"
+            token = { env = "DMT_TEST_MISSING_TOKEN" }
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "rss"
+            [targets.requestHeaders]
+            Cookie = "PHPSESSID=${DMT_TEST_MISSING_SESSION}"
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err().to_string();
+
+        assert!(error.contains(
+            "token.env refers to environment variable DMT_TEST_MISSING_TOKEN which is not set"
+        ));
+        assert!(error.contains("targets[0] \"Kusunoki Debut\": requestHeaders.Cookie refers to environment variable DMT_TEST_MISSING_SESSION which is not set"));
+
+        fs::remove_file(&token_path).unwrap();
+    }
+}
@@ -0,0 +1,1021 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono_tz::Tz;
+use crony::Schedule;
+use toml::Value as TomlValue;
+use url::Url;
+
+use crate::{
+    parsers::{
+        html::read_html_field,
+        parser::{describe_modes, describe_modes_where, find_parser, Parser, PARSERS},
+        readers::{parse_json_path, read_json_date_format},
+        template::TitleTemplate,
+    },
+    structs::{
+        DetailRules, PageStrategy, ParseMode, ParseRules, Target, TargetDetail, TargetPagination,
+        TargetTransforms,
+    },
+};
+
+use super::{
+    include::{
+        find_include_files, find_target_files, include_targets, read_toml_file,
+        resolve_target_files, INCLUDED_FROM_KEY,
+    },
+    presets::apply_preset,
+    reader::{into_result, ErrorList, TableReader},
+    secrets::{read_secret, read_secret_map},
+    transforms::read_transforms,
+};
+
+/// Schedule used when `cron` is not set (once every 10 AM JST).
+const DEFAULT_CRON: &str = "0 0 1 * * *";
+
+/// Settings keys that can be overridden by a `DMT_*` environment variable,
+/// e.g. `DMT_TOKEN` for `token`.
+const ENV_OVERRIDABLE_KEYS: [&str; 2] = ["token", "cron"];
+
+/// Keys allowed at the root of the settings file.
+pub const ROOT_KEYS: [&str; 4] = ["token", "cron", "includeDir", "targets"];
+
+/// Keys allowed in a target, besides the tables of the parsers (see `parser_tables`).
+pub const TARGET_KEYS: [&str; 18] = [
+    "name",
+    "preset",
+    "work",
+    "source",
+    "ascendingSource",
+    "mode",
+    "baseUrl",
+    "requestHeaders",
+    "delay",
+    "timezone",
+    "cron",
+    "enabled",
+    "servers",
+    "excludeServers",
+    "pagination",
+    "detail",
+    "transforms",
+    "titleTemplate",
+];
+
+/// Keys allowed in the `pagination` table of a target.
+pub const TARGET_PAGINATION_KEYS: [&str; 8] = [
+    "pageParameter",
+    "startPage",
+    "nextTag",
+    "nextAttribute",
+    "cursor",
+    "cursorParameter",
+    "maxPages",
+    "stopOnKnown",
+];
+
+/// Keys that each pick a pagination strategy, only one of them can be set.
+const PAGE_STRATEGY_KEYS: [&str; 3] = ["pageParameter", "nextTag", "cursor"];
+
+/// Most pages fetched in a run when `maxPages` is not set.
+const DEFAULT_MAX_PAGES: u32 = 10;
+
+/// Keys allowed in the `detail` table of a target.
+pub const TARGET_DETAIL_KEYS: [&str; 6] =
+    ["mode", "limit", "title", "date", "dateFormat", "thumbnail"];
+
+/// Most chapter pages fetched in a run when the detail `limit` is not set.
+const DEFAULT_DETAIL_LIMIT: u32 = 10;
+
+/// Every value the bot needs from the settings file, already validated.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub token: String,
+    /// Expression of the global fetch schedule, already checked to be valid.
+    pub cron: String,
+    pub targets: Vec<Target>,
+}
+
+/// Finds the configuration file, looking next to the executable first
+/// and then in the current working directory.
+pub fn find_config_file(filename: &str) -> Result<PathBuf> {
+    let mut exe_path = env::current_exe()?;
+    exe_path.pop();
+    exe_path.push(filename);
+
+    let mut cwd_path = env::current_dir()?;
+    cwd_path.push(filename);
+
+    let tries = vec![exe_path, cwd_path];
+
+    for path in tries {
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+
+    bail!("Missing config file ({}).", filename)
+}
+
+/// Parses the entire configuration TOML file,
+/// including the targets of every file inside its `includeDir`.
+/// If the filename is not specified, uses "settings.toml" as default.
+pub fn get_config(filename: Option<&str>) -> Result<TomlValue> {
+    let filename = filename.unwrap_or("settings.toml");
+    let path = find_config_file(filename)?;
+
+    let mut config = read_toml_file(&path)?;
+    let include_files = find_include_files(&path, &config)?;
+    include_targets(&mut config, &path, &include_files)?;
+    resolve_target_files(&mut config, get_config_dir(&path));
+
+    Ok(config)
+}
+
+/// Lists the main configuration file, every file included by it and the files used by its targets.
+pub fn get_config_files(filename: &str) -> Result<Vec<PathBuf>> {
+    let path = find_config_file(filename)?;
+    let config = read_toml_file(&path)?;
+    let config_dir = get_config_dir(&path);
+
+    let include_files = find_include_files(&path, &config)?;
+    let mut target_files = find_target_files(&config, config_dir);
+    for include_file in &include_files {
+        let included = read_toml_file(include_file)?;
+        target_files.extend(find_target_files(&included, config_dir));
+    }
+
+    let mut files = vec![path.clone()];
+    files.extend(include_files);
+    files.extend(target_files);
+
+    Ok(files)
+}
+
+/// Directory relative paths of the config are resolved from.
+fn get_config_dir(config_path: &Path) -> &Path {
+    config_path.parent().unwrap_or(Path::new(""))
+}
+
+/// Reads the configuration file, applies the environment variable and token file overrides,
+/// and validates it into `Settings`.
+pub fn load_settings(filename: &str, token_file: Option<&str>) -> Result<Settings> {
+    let mut config = get_config(Some(filename))?;
+    apply_env_overrides(&mut config, env::vars());
+    if let Some(token_file) = token_file {
+        override_setting(&mut config, "token", read_token_file(token_file)?);
+    }
+
+    get_settings(&config)
+}
+
+/// Replaces the value of a top-level settings key.
+pub fn override_setting(config: &mut TomlValue, key: &str, value: String) {
+    if let Some(root) = config.as_table_mut() {
+        root.insert(key.to_owned(), TomlValue::String(value));
+    }
+}
+
+/// Overrides settings keys with the values of their `DMT_*` environment variables.
+/// Takes the variables as a parameter so it can be fed `std::env::vars()`.
+pub fn apply_env_overrides(
+    config: &mut TomlValue,
+    vars: impl IntoIterator<Item = (String, String)>,
+) {
+    for (name, value) in vars {
+        let key = match name.strip_prefix("DMT_") {
+            Some(key) => key.to_lowercase(),
+            None => continue,
+        };
+
+        if ENV_OVERRIDABLE_KEYS.contains(&key.as_str()) {
+            override_setting(config, &key, value);
+        }
+    }
+}
+
+/// Reads the Discord token from a file, ignoring surrounding whitespace.
+pub fn read_token_file(path: &str) -> Result<String> {
+    let token = fs::read_to_string(path)
+        .map_err(|error| anyhow!("Could not read token file {} ({}).", path, error))?;
+
+    Ok(token.trim().to_owned())
+}
+
+/// Validates the parsed configuration file and converts it into `Settings`.
+/// All problems in the file are reported together in a single error.
+pub fn get_settings(config: &TomlValue) -> Result<Settings> {
+    let errors = ErrorList::default();
+
+    let root = match config.as_table() {
+        Some(root) => root,
+        None => bail!("Config file is not a table."),
+    };
+    let reader = TableReader::new(root, "", &errors);
+    reader.deny_unknown_keys(&ROOT_KEYS);
+
+    let token = match reader.has("token") {
+        true => read_secret(&reader, "token"),
+        false => {
+            reader.error("token", "is required");
+            None
+        }
+    };
+    // Already used by `get_config`, only checked here
+    reader.string("includeDir");
+    let cron = match reader.has("cron") {
+        true => read_cron_schedule(&reader),
+        false => Some(DEFAULT_CRON.to_owned()),
+    };
+    let targets = read_targets(&reader);
+
+    into_result(errors)?;
+
+    Ok(Settings {
+        token: token.unwrap(),
+        cron: cron.unwrap(),
+        targets: targets.unwrap(),
+    })
+}
+
+/// Reads the `cron` expression of a table, checking that it is a valid schedule.
+/// Returns `None` if it is not set or not valid.
+fn read_cron_schedule(reader: &TableReader) -> Option<String> {
+    let expression = reader.string("cron")?;
+
+    match expression.parse::<Schedule>() {
+        Ok(_) => Some(expression),
+        Err(error) => {
+            reader.error(
+                "cron",
+                format!("is not a valid cron expression ({})", error),
+            );
+            None
+        }
+    }
+}
+
+/// Reads and validates the array of gofer targets.
+/// Returns `None` if any of them is invalid.
+fn read_targets(reader: &TableReader) -> Option<Vec<Target>> {
+    let config_targets = match reader.get("targets") {
+        Some(TomlValue::Array(config_targets)) => config_targets,
+        Some(_) => {
+            reader.error("targets", "must be an array of tables");
+            return None;
+        }
+        None => {
+            reader.error("targets", "is required");
+            return None;
+        }
+    };
+
+    let mut targets = vec![];
+    let mut valid = true;
+    for (index, config_target) in config_targets.iter().enumerate() {
+        let table = match config_target.as_table() {
+            Some(table) => table,
+            None => {
+                reader.error(&format!("targets[{}]", index), "must be a table");
+                valid = false;
+                continue;
+            }
+        };
+
+        // Targets of included files are reported with their file and their index in it
+        let mut table = table.to_owned();
+        let origin = match table.remove(INCLUDED_FROM_KEY) {
+            Some(origin) => format!(
+                "{}: targets[{}]",
+                origin
+                    .get("file")
+                    .and_then(|file| file.as_str())
+                    .unwrap_or(""),
+                origin
+                    .get("index")
+                    .and_then(|index| index.as_integer())
+                    .unwrap_or(0),
+            ),
+            None => format!("targets[{}]", index),
+        };
+        let context = match table.get("name").and_then(|name| name.as_str()) {
+            Some(name) => format!("{} \"{}\"", origin, name),
+            None => origin,
+        };
+        let target_reader = TableReader::new(&table, &context, reader.errors());
+
+        // Targets using a preset are read from the expanded table instead
+        let expanded;
+        let target_reader = match table.contains_key("preset") {
+            true => match apply_preset(&target_reader, &table) {
+                Some(expanded_table) => {
+                    expanded = expanded_table;
+                    TableReader::new(&expanded, &context, reader.errors())
+                }
+                None => {
+                    valid = false;
+                    continue;
+                }
+            },
+            false => target_reader,
+        };
+
+        match read_target(&target_reader) {
+            Some(target) => targets.push(target),
+            None => valid = false,
+        }
+    }
+
+    valid.then_some(targets)
+}
+
+/// Lists the tables read by any of the parsers, like `keys` or `feed`.
+pub fn parser_tables() -> Vec<&'static str> {
+    let mut tables = vec![];
+    for table in PARSERS.iter().flat_map(|parser| parser.tables()) {
+        if !tables.contains(table) {
+            tables.push(*table);
+        }
+    }
+
+    tables
+}
+
+/// Reads a single target. Returns `None` if any of its values is invalid.
+fn read_target(reader: &TableReader) -> Option<Target> {
+    let parser_tables = parser_tables();
+    reader.deny_unknown_keys(&[TARGET_KEYS.as_slice(), &parser_tables].concat());
+
+    let name = reader.required_string("name");
+
+    if reader.has("work") && !reader.has("preset") {
+        reader.error("work", "is only used together with preset");
+    }
+
+    let source = reader.required_string("source");
+    if let Some(source) = &source {
+        if let Err(error) = Url::parse(source) {
+            reader.error("source", format!("is not a valid URL ({})", error));
+        }
+    }
+
+    let ascending_source = reader.bool("ascendingSource");
+
+    let mode_name = reader.required_string("mode");
+    let mode = mode_name
+        .as_deref()
+        .and_then(|mode_name| match find_parser(mode_name) {
+            Some(parser) => Some(ParseMode(parser)),
+            None => {
+                reader.error(
+                    "mode",
+                    format!(
+                        "must be one of {} (found \"{}\")",
+                        describe_modes(),
+                        mode_name
+                    ),
+                );
+                None
+            }
+        });
+
+    let base_url = reader.string("baseUrl");
+    let request_headers = read_secret_map(reader, "requestHeaders");
+    let delay = reader.integer("delay", 0, u8::MAX.into());
+    let timezone = read_timezone(reader);
+    let cron = read_cron_schedule(reader);
+    let enabled = reader.bool("enabled");
+    let servers = read_server_ids(reader, "servers");
+    let exclude_servers = read_server_ids(reader, "excludeServers");
+
+    let title_template = read_title_template(reader);
+    let rules = mode.and_then(|mode| read_parse_rules(reader, mode, &parser_tables));
+    let pagination = match reader.has("pagination") {
+        true => read_pagination(reader, mode.map(|mode| mode.parser())).map(Some),
+        false => Some(None),
+    };
+    let detail = match reader.has("detail") {
+        true => read_detail(reader).map(Some),
+        false => Some(None),
+    };
+    let transforms = match reader.has("transforms") {
+        true => reader
+            .table("transforms")
+            .and_then(|transforms| read_transforms(&transforms)),
+        false => Some(TargetTransforms::default()),
+    };
+
+    // Every field in the title template must be one the mode can extract
+    if let (Some(template), Some(mode)) = (&title_template, &mode) {
+        let no_rules = ParseRules::new(());
+        let rules = rules.as_ref().unwrap_or(&no_rules);
+        for name in template.field_names() {
+            if !matches!(name, "number" | "title") && !mode.parser().has_field(rules, name) {
+                reader.error(
+                    "titleTemplate",
+                    format!("refers to unknown field \"{}\"", name),
+                );
+            }
+        }
+    }
+
+    Some(Target {
+        name: name?,
+        source: source?,
+        ascending_source: ascending_source.unwrap_or(false),
+        mode: mode?,
+        base_url,
+        request_headers,
+        delay: delay.map(|delay| delay as u8),
+        timezone,
+        cron,
+        enabled: enabled.unwrap_or(true),
+        servers,
+        exclude_servers,
+        rules: rules?,
+        pagination: pagination?,
+        detail: detail?,
+        transforms: transforms?,
+        title_template,
+    })
+}
+
+/// Reads the tables of a target used by the parser of its mode,
+/// checking that the required ones are set and that those of other modes are not.
+fn read_parse_rules(
+    reader: &TableReader,
+    mode: ParseMode,
+    parser_tables: &[&str],
+) -> Option<ParseRules> {
+    let parser = mode.parser();
+    let mut valid = true;
+    for table in parser.required_tables() {
+        if !reader.has(table) {
+            reader.error(table, format!("is required for mode \"{}\"", mode.name()));
+            valid = false;
+        }
+    }
+    for table in parser_tables {
+        if reader.has(table) && !parser.tables().contains(table) {
+            reader.error(table, format!("is not used by mode \"{}\"", mode.name()));
+            valid = false;
+        }
+    }
+
+    let rules = parser.read_rules(reader, reader.has("titleTemplate"));
+    rules.filter(|_| valid)
+}
+
+/// Reads and parses the title template of a target.
+fn read_title_template(reader: &TableReader) -> Option<TitleTemplate> {
+    let template = reader.string("titleTemplate")?;
+    match TitleTemplate::parse(&template) {
+        Ok(template) => Some(template),
+        Err(error) => {
+            reader.error("titleTemplate", error);
+            None
+        }
+    }
+}
+
+/// Reads a list of guild IDs, written either as strings or as integers.
+fn read_server_ids(reader: &TableReader, key: &str) -> Option<Vec<String>> {
+    let array = match reader.get(key)? {
+        TomlValue::Array(array) => array,
+        _ => {
+            reader.error(key, "must be an array of guild IDs");
+            return None;
+        }
+    };
+
+    let mut ids = vec![];
+    for item in array {
+        let id = match item {
+            TomlValue::String(string) => string.to_owned(),
+            TomlValue::Integer(integer) => integer.to_string(),
+            _ => {
+                reader.error(key, "must be an array of guild IDs");
+                return None;
+            }
+        };
+
+        if id.is_empty() || !id.chars().all(|character| character.is_ascii_digit()) {
+            reader.error(key, format!("has an invalid guild ID (found \"{}\")", id));
+            return None;
+        }
+        ids.push(id);
+    }
+
+    Some(ids)
+}
+
+/// Reads the rules for the page of each new chapter. Its `mode` is either "html" (the default)
+/// or "json", and decides whether the values are tags or JSON paths.
+fn read_detail(reader: &TableReader) -> Option<TargetDetail> {
+    let detail = reader.table("detail")?;
+    detail.deny_unknown_keys(&TARGET_DETAIL_KEYS);
+
+    if !["title", "date", "thumbnail"]
+        .into_iter()
+        .any(|key| detail.has(key))
+    {
+        reader.error("detail", "must have at least one of title, date, thumbnail");
+    }
+
+    let json = match detail.string("mode").as_deref() {
+        Some("html") => Some(false),
+        Some("json") => Some(true),
+        Some(other) => {
+            detail.error(
+                "mode",
+                format!("must be \"html\" or \"json\" (found \"{}\")", other),
+            );
+            None
+        }
+        None if detail.has("mode") => None,
+        None => Some(false),
+    };
+
+    let rules = match json? {
+        true => {
+            let path = |key: &str| match detail.non_empty_string(key) {
+                Some(path) => parse_json_path(&detail, key, &path).map(Some),
+                None => Some(None),
+            };
+            let title = path("title");
+            let date = path("date");
+            let thumbnail = path("thumbnail");
+            DetailRules::Json {
+                title: title?,
+                date: date?,
+                date_format: read_json_date_format(&detail),
+                thumbnail: thumbnail?,
+            }
+        }
+        false => {
+            let field = |key: &str| match detail.has(key) {
+                true => read_html_field(&detail, key).map(Some),
+                false => Some(None),
+            };
+            let title = field("title");
+            let date = field("date");
+            let thumbnail = field("thumbnail");
+            DetailRules::Html {
+                title: title?,
+                date: date?,
+                date_format: detail.non_empty_string("dateFormat"),
+                thumbnail: thumbnail?,
+            }
+        }
+    };
+
+    let limit = match detail.has("limit") {
+        true => detail.integer("limit", 1, u32::MAX.into()),
+        false => Some(DEFAULT_DETAIL_LIMIT.into()),
+    };
+
+    Some(TargetDetail {
+        rules,
+        limit: limit? as u32,
+    })
+}
+
+/// Reads how the source of a target is paginated.
+/// The strategy is picked by setting exactly one of `pageParameter`, `nextTag` or `cursor`,
+/// and has to be one the parser of the mode can follow.
+fn read_pagination(reader: &TableReader, parser: Option<&dyn Parser>) -> Option<TargetPagination> {
+    let pagination = reader.table("pagination")?;
+    pagination.deny_unknown_keys(&TARGET_PAGINATION_KEYS);
+
+    let strategies: Vec<&str> = PAGE_STRATEGY_KEYS
+        .into_iter()
+        .filter(|key| pagination.has(key))
+        .collect();
+    if let (Some(parser), [key]) = (parser, strategies.as_slice()) {
+        if *key != "pageParameter" && !parser.page_strategies().contains(key) {
+            let modes = describe_modes_where(|parser| parser.page_strategies().contains(key));
+            pagination.error(key, format!("is only supported by mode {}", modes));
+        }
+    }
+    let strategy = match strategies.as_slice() {
+        ["pageParameter"] => {
+            let parameter = pagination.string("pageParameter");
+            let start = match pagination.has("startPage") {
+                true => pagination.integer("startPage", 0, u32::MAX.into()),
+                false => Some(1),
+            };
+            Some(PageStrategy::Number {
+                parameter: parameter?,
+                start: start? as u32,
+            })
+        }
+        ["nextTag"] => {
+            let tag = pagination.string("nextTag");
+            let attribute = pagination.non_empty_string("nextAttribute");
+            Some(PageStrategy::NextLink {
+                tag: tag?,
+                attribute: attribute.unwrap_or(String::from("href")),
+            })
+        }
+        ["cursor"] => {
+            let path = pagination
+                .string("cursor")
+                .and_then(|path| parse_json_path(&pagination, "cursor", &path));
+            let parameter = pagination.non_empty_string("cursorParameter");
+            Some(PageStrategy::Cursor {
+                path: path?,
+                parameter,
+            })
+        }
+        _ => {
+            reader.error(
+                "pagination",
+                format!("must have exactly one of {}", PAGE_STRATEGY_KEYS.join(", ")),
+            );
+            None
+        }
+    };
+
+    let max_pages = match pagination.has("maxPages") {
+        true => pagination.integer("maxPages", 1, u32::MAX.into()),
+        false => Some(DEFAULT_MAX_PAGES.into()),
+    };
+    let stop_on_known = match pagination.has("stopOnKnown") {
+        true => pagination.bool("stopOnKnown"),
+        false => Some(false),
+    };
+
+    Some(TargetPagination {
+        strategy: strategy?,
+        max_pages: max_pages? as u32,
+        stop_on_known: stop_on_known?,
+    })
+}
+
+/// Reads the IANA time zone (e.g. `Asia/Tokyo`) used for dates written without an offset.
+fn read_timezone(reader: &TableReader) -> Option<Tz> {
+    let name = reader.string("timezone")?;
+    match name.parse::<Tz>() {
+        Ok(timezone) => Some(timezone),
+        Err(_) => {
+            reader.error(
+                "timezone",
+                format!(
+                    "is not a known time zone, like \"Asia/Tokyo\" (found \"{}\")",
+                    name
+                ),
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value as TomlValue;
+
+    use crate::{
+        config::reader::InvalidConfig,
+        structs::{DetailRules, HtmlField, PageStrategy, TargetDetail, TargetPagination},
+    };
+
+    use super::{apply_env_overrides, get_settings};
+
+    #[test]
+    fn test_get_settings_from_sample() {
+        let config: TomlValue = include_str!("../../settings.sample.toml").parse().unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        assert_eq!(settings.targets.len(), 4);
+        assert_eq!(settings.targets[0].name, "Bokuyaba");
+        assert_eq!(settings.targets[0].mode.name(), "html");
+        // Header values are kept as plain strings
+        assert_eq!(
+            settings.targets[2].request_headers.as_ref().unwrap()["X-Requested-With"],
+            "pixivcomic",
+        );
+    }
+
+    #[test]
+    fn test_get_settings_reports_every_error() {
+        let config: TomlValue = r###"
+            cron = "not a cron"
+
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2"
+            mode = 1
+            ascending_source = true
+            requestHeaders = "X-Requested-With: pixivcomic"
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "html"
+            [targets.tags]
+            dateFormat = 10
+            [targets.feed]
+            missingTitle = "skip"
+
+            [[targets]]
+            name = "Idol x Idol Story"
+            source = "https://comic-fuz.com/manga/3140"
+            mode = "json"
+            [targets.keys]
+            chapters = "props.chapters[*"
+            number = "chapterId"
+            title = "chapterMainName"
+            date = "updatedDate"
+            url = "chapterId"
+        "###
+        .parse()
+        .unwrap();
+
+        let error = get_settings(&config).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+
+        assert_eq!(
+            error.errors,
+            vec![
+                "token is required",
+                "cron is not a valid cron expression (Invalid expression: Invalid cron expression.)",
+                "targets[0] \"Bokuyaba\": ascending_source is not a known key (did you mean ascendingSource?)",
+                "targets[0] \"Bokuyaba\": mode must be a string",
+                "targets[0] \"Bokuyaba\": requestHeaders must be a table",
+                "targets[1] \"Kusunoki Debut\": feed is not used by mode \"html\"",
+                "targets[1] \"Kusunoki Debut\": tags.chaptersTag is required",
+                "targets[1] \"Kusunoki Debut\": tags.dateFormat must be a string",
+                "targets[2] \"Idol x Idol Story\": keys.chapters is not a valid JSON path (\"props.chapters[*\": \"[\" without a closing \"]\")",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_target_servers() {
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/rss"
+            mode = "rss"
+            servers = ["1011", 1012]
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            excludeServers = ["1012"]
+            ascendingSource = true
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "rss"
+            enabled = false
+        "###
+        .parse()
+        .unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        let announced_on = |guild_id: &str| -> Vec<&str> {
+            settings
+                .targets
+                .iter()
+                .filter(|target| target.is_announced_on(guild_id))
+                .map(|target| target.name.as_str())
+                .collect()
+        };
+        assert_eq!(announced_on("1011"), vec!["Bokuyaba", "Shounen wo Kau"]);
+        assert_eq!(announced_on("1012"), vec!["Bokuyaba"]);
+        assert_eq!(announced_on("1013"), vec!["Shounen wo Kau"]);
+        // `ascendingSource` is read from the camelCase key and defaults to false
+        let ascending: Vec<bool> = settings
+            .targets
+            .iter()
+            .map(|target| target.ascending_source)
+            .collect();
+        assert_eq!(ascending, vec![false, true, false]);
+
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/rss"
+            mode = "rss"
+            servers = ["my server"]
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err().to_string();
+
+        assert!(error.contains(
+            "targets[0] \"Bokuyaba\": servers has an invalid guild ID (found \"my server\")"
+        ));
+    }
+
+    #[test]
+    fn test_title_template_fields() {
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "json"
+            titleTemplate = "{number}[: {subtitle}]"
+            [targets.keys]
+            chapters = "data.episodes"
+            number = "episode.numbering_title"
+            date = "episode.read_start_at"
+            url = "episode.viewer_path"
+            [targets.keys.fields]
+            subtitle = "episode.sub_title"
+
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/rss"
+            mode = "rss"
+            titleTemplate = "{title}[ - {subtitle}]"
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+
+        assert_eq!(
+            error.errors,
+            vec!["targets[1] \"Bokuyaba\": titleTemplate refers to unknown field \"subtitle\""]
+        );
+    }
+
+    #[test]
+    fn test_read_feed() {
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            titleTemplate = "{giga:episodeNumber}[: {title}]"
+            [targets.feed]
+            date = ["updated", "pubDate"]
+            url = "link:alternate"
+
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/rss"
+            mode = "rss"
+            [targets.feed]
+            number = "episode"
+            missingTitle = "ignore"
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+
+        assert_eq!(
+            error.errors,
+            vec![
+                "targets[1] \"Bokuyaba\": feed.number has an unknown feed field \"episode\"",
+                "targets[1] \"Bokuyaba\": feed.missingTitle must be \"skip\" or \"error\" (found \"ignore\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_pagination() {
+        let config: TomlValue = include_str!("../../settings.sample.toml").parse().unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        assert_eq!(
+            settings.targets[0].pagination,
+            Some(TargetPagination {
+                strategy: PageStrategy::Number {
+                    parameter: "page".into(),
+                    start: 0,
+                },
+                max_pages: 10,
+                stop_on_known: true,
+            })
+        );
+
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            [targets.pagination]
+            nextTag = "a.next"
+            maxPages = 0
+
+            [[targets]]
+            name = "Bokuyaba"
+            source = "https://championcross.jp/series/899dda204c3f2/pagingList"
+            mode = "html"
+            [targets.tags]
+            chaptersTag = "div.series-ep-list-item"
+            [targets.pagination]
+            pageParameter = "page"
+            cursor = "next"
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+
+        assert_eq!(
+            error.errors,
+            vec![
+                "targets[0] \"Shounen wo Kau\": pagination.nextTag is only supported by mode \"html\" or \"json_in_html\"",
+                "targets[0] \"Shounen wo Kau\": pagination.maxPages must be between 1 and 4294967295",
+                "targets[1] \"Bokuyaba\": pagination must have exactly one of pageParameter, nextTag, cursor",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_detail() {
+        let source = r###"
+            token = ""
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            [targets.detail]
+            date = { tag = "time", attribute = "datetime" }
+            dateFormat = "%Y-%m-%d %H:%M"
+            limit = 5
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            preset = "pixiv-comic"
+            work = "8789"
+            [targets.detail]
+            mode = "xml"
+            limit = 0
+        "###;
+        let config: TomlValue = source.parse().unwrap();
+        let error = get_settings(&config).unwrap_err();
+        let error = error.downcast::<InvalidConfig>().unwrap();
+
+        assert_eq!(
+            error.errors,
+            vec![
+                "targets[1] \"Kusunoki Debut\": detail must have at least one of title, date, thumbnail",
+                "targets[1] \"Kusunoki Debut\": detail.mode must be \"html\" or \"json\" (found \"xml\")",
+            ]
+        );
+
+        let source = source.replace(
+            "mode = \"xml\"\n            limit = 0",
+            "mode = \"json\"\n            title = \"data.episode.title\"",
+        );
+        let config: TomlValue = source.parse().unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        assert_eq!(
+            settings.targets[0].detail,
+            Some(TargetDetail {
+                rules: DetailRules::Html {
+                    title: None,
+                    date: Some(HtmlField {
+                        tag: Some("time".into()),
+                        attribute: Some("datetime".into()),
+                    }),
+                    date_format: Some("%Y-%m-%d %H:%M".into()),
+                    thumbnail: None,
+                },
+                limit: 5,
+            })
+        );
+        assert_eq!(settings.targets[1].detail.as_ref().unwrap().limit, 10);
+    }
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let mut config: TomlValue = include_str!("../../settings.sample.toml").parse().unwrap();
+        apply_env_overrides(
+            &mut config,
+            vec![
+                ("DMT_TOKEN".into(), "secret".into()),
+                ("DMT_CRON".into(), "0 30 * * * *".into()),
+                ("DMT_TARGETS".into(), "ignored".into()),
+                ("TOKEN".into(), "ignored".into()),
+            ],
+        );
+        let settings = get_settings(&config).unwrap();
+
+        assert_eq!(settings.token, "secret");
+        assert_eq!(settings.targets.len(), 4);
+    }
+}
@@ -0,0 +1,164 @@
+use regex::Regex;
+use toml::Value as TomlValue;
+
+use crate::structs::{ChapterField, TargetTransforms, TransformStep};
+
+use super::reader::TableReader;
+
+/// Keys allowed in the `transforms` table of a target.
+pub const TRANSFORM_FIELDS: [&str; 3] = ["number", "title", "url"];
+
+/// Steps written as a plain string since they take no argument.
+pub const SIMPLE_STEPS: [&str; 3] = ["trim", "collapseWhitespace", "lowercase"];
+
+/// Reads the post-processing steps of every chapter field, e.g.
+/// `number = [{ from = "title" }, { regex = "第(\\d+)話" }, "trim"]`.
+/// Returns `None` if any step is invalid.
+pub fn read_transforms(reader: &TableReader) -> Option<TargetTransforms> {
+    reader.deny_unknown_keys(&TRANSFORM_FIELDS);
+
+    let number = read_steps(reader, "number");
+    let title = read_steps(reader, "title");
+    let url = read_steps(reader, "url");
+
+    Some(TargetTransforms {
+        number: number?,
+        title: title?,
+        url: url?,
+    })
+}
+
+fn read_steps(reader: &TableReader, key: &str) -> Option<Vec<TransformStep>> {
+    let array = match reader.get(key) {
+        Some(TomlValue::Array(array)) => array,
+        Some(_) => {
+            reader.error(key, "must be an array of steps");
+            return None;
+        }
+        None => return Some(vec![]),
+    };
+
+    let mut steps = vec![];
+    let mut valid = true;
+    for (index, item) in array.iter().enumerate() {
+        let location = format!("{}[{}]", key, index);
+        match read_step(item) {
+            Ok(step) => steps.push(step),
+            Err(message) => {
+                reader.error(&location, message);
+                valid = false;
+            }
+        }
+    }
+
+    valid.then_some(steps)
+}
+
+fn read_step(item: &TomlValue) -> Result<TransformStep, String> {
+    let table = match item {
+        TomlValue::String(name) => {
+            return match name.as_str() {
+                "trim" => Ok(TransformStep::Trim),
+                "collapseWhitespace" => Ok(TransformStep::CollapseWhitespace),
+                "lowercase" => Ok(TransformStep::Lowercase),
+                other => Err(format!(
+                    "must be one of \"{}\" or a table (found \"{}\")",
+                    SIMPLE_STEPS.join("\", \""),
+                    other
+                )),
+            }
+        }
+        TomlValue::Table(table) => table,
+        _ => return Err(String::from("must be a string or a table")),
+    };
+
+    let string = |key: &str| -> Result<String, String> {
+        match table.get(key) {
+            Some(TomlValue::String(string)) => Ok(string.to_owned()),
+            Some(_) => Err(format!("{} must be a string", key)),
+            None => Ok(String::new()),
+        }
+    };
+    let regex = |key: &str| -> Result<Regex, String> {
+        let pattern = string(key)?;
+        Regex::new(&pattern).map_err(|error| format!("{} is not a valid regex ({})", key, error))
+    };
+
+    let mut keys: Vec<&str> = table.keys().map(String::as_str).collect();
+    keys.sort();
+    match keys.as_slice() {
+        ["from"] => match string("from")?.as_str() {
+            "number" => Ok(TransformStep::From(ChapterField::Number)),
+            "title" => Ok(TransformStep::From(ChapterField::Title)),
+            "url" => Ok(TransformStep::From(ChapterField::Url)),
+            other => Err(format!(
+                "from must be one of \"number\", \"title\" or \"url\" (found \"{}\")",
+                other
+            )),
+        },
+        ["regex"] => Ok(TransformStep::Regex(regex("regex")?)),
+        ["replace"] | ["replace", "with"] => {
+            Ok(TransformStep::Replace(regex("replace")?, string("with")?))
+        }
+        ["prefix"] => Ok(TransformStep::Prefix(string("prefix")?)),
+        ["suffix"] => Ok(TransformStep::Suffix(string("suffix")?)),
+        _ => Err(String::from(
+            "must have exactly one of from, regex, replace (with with), prefix or suffix",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value as TomlValue;
+
+    use crate::{
+        config::settings::get_settings,
+        structs::{ChapterField, TransformStep},
+    };
+
+    #[test]
+    fn test_read_transforms() {
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            [targets.transforms]
+            number = [{ from = "title" }, { regex = "第(\\d+)話" }]
+            title = ["collapseWhitespace", { replace = "^【.*?】", with = "" }]
+        "###
+        .parse()
+        .unwrap();
+        let settings = get_settings(&config).unwrap();
+
+        let transforms = &settings.targets[0].transforms;
+        assert_eq!(transforms.number.len(), 2);
+        assert_eq!(
+            transforms.number[0],
+            TransformStep::From(ChapterField::Title)
+        );
+        assert_eq!(transforms.title[0], TransformStep::CollapseWhitespace);
+        assert!(transforms.url.is_empty());
+
+        let config: TomlValue = r###"
+            token = ""
+
+            [[targets]]
+            name = "Shounen wo Kau"
+            source = "https://comic-zenon.com/rss/series/13933686331687311931"
+            mode = "rss"
+            [targets.transforms]
+            number = [{ regex = "第(\\d+話" }, "uppercase", { prefix = "Ch. ", suffix = "!" }]
+        "###
+        .parse()
+        .unwrap();
+        let error = get_settings(&config).unwrap_err().to_string();
+
+        assert!(error.contains("transforms.number[0] regex is not a valid regex"));
+        assert!(error.contains("transforms.number[1] must be one of \"trim\", \"collapseWhitespace\", \"lowercase\" or a table (found \"uppercase\")"));
+        assert!(error.contains("transforms.number[2] must have exactly one of"));
+    }
+}
@@ -1,2 +1,3 @@
-pub mod database;
-pub mod sqlite;
+#[allow(clippy::module_inception)]
+pub mod database;
+pub mod sqlite;
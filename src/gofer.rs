@@ -1,127 +1,374 @@
-use std::{collections::HashMap, sync::Arc};
-
-use anyhow::Result;
-use colored::Colorize;
-use reqwest::Client;
-use tokio::task::JoinSet;
-
-use crate::{
-    database::database::Database,
-    log,
-    parsers::{
-        html::parse_html, json::parse_json, json_in_html::parse_json_in_html, rss::parse_rss,
-    },
-    structs::{Chapter, ParseMode, Target},
-    Worker,
-};
-
-/// Spawns one thread for each Target,
-/// making a HTTP request and then parsing the contents.
-/// If there are new Chapters, saves them to the database.
-pub async fn dispatch_gofers(
-    database: Arc<dyn Database>,
-    targets: Vec<Target>,
-) -> (Worker, Result<()>) {
-    log!("{} Dispatching Gofers...", "[GOFR]".green());
-
-    let mut handles = JoinSet::new();
-
-    for target in targets {
-        let cloned_db_ref = database.clone();
-        handles.spawn(run_gofer(cloned_db_ref, target.clone()));
-    }
-
-    while let Some(_) = handles.join_next().await {
-        // Loop until all handles have finished
-    }
-
-    log!("{} All Gofers have returned.", "[GOFR]".green());
-    (Worker::Gofer, Ok(()))
-}
-
-/// Child process of `dispatch_gofers`.
-/// This function gets run for every thread.
-pub async fn run_gofer(database: Arc<dyn Database>, target: Target) -> Result<()> {
-    log!("{} Gofer started for {}...", "[GOFR]".green(), target.name);
-    let mut chapters: Option<Vec<Chapter>> = None;
-
-    let mut attempts = 5;
-    while attempts > 0 {
-        let fetch = fetch_chapters(&target).await;
-        if fetch.is_ok() {
-            chapters = Some(fetch.unwrap());
-            break;
-        } else {
-            log!(
-                "{} Gofer for {} encountered an error: {}",
-                "[GOFR]".green(),
-                target.name,
-                fetch.unwrap_err()
-            );
-        }
-
-        attempts -= 1;
-    }
-
-    if attempts == 0 {
-        log!(
-            "{} {}: Failed all fetching attempts.",
-            "[GOFR]".green(),
-            target.name,
-        );
-    }
-
-    if chapters.is_some() {
-        let chapters_ref = &chapters.unwrap();
-        let mut attempts = 5;
-        while attempts > 0 {
-            let save = database.save_chapters(chapters_ref.as_slice()).await;
-            if save.is_ok() {
-                break;
-            }
-            attempts -= 1;
-        }
-
-        if attempts == 0 {
-            log!(
-                "{} {}: Failed saving chapters.",
-                "[GOFR]".green(),
-                target.name,
-            );
-        }
-    }
-
-    log!("{} {}: Gofer finished.", "[GOFR]".green(), target.name);
-
-    Ok(())
-}
-
-/// Makes a HTTP request to get the response body from a Target's `source`,
-/// and then parses the body using the defined `mode`.
-async fn fetch_chapters(target: &Target) -> Result<Vec<Chapter>> {
-    let body = fetch_body(&target.source, &target.request_headers).await?;
-
-    let chapters = match target.mode {
-        ParseMode::Rss => parse_rss(target, &body)?,
-        ParseMode::Json => parse_json(target, &body)?,
-        ParseMode::Html => parse_html(target, &body)?,
-        ParseMode::JsonInHtml => parse_json_in_html(target, &body)?,
-    };
-
-    Ok(chapters)
-}
-
-/// Makes a HTTP request to get the response body from a given URL.
-/// Can optionally supply headers.
-async fn fetch_body(url: &str, headers: &Option<HashMap<String, String>>) -> Result<String> {
-    let client = Client::builder().gzip(true).brotli(true).build()?;
-    let mut request = client.get(url);
-    if headers.is_some() {
-        for header in headers.as_ref().unwrap() {
-            request = request.header(header.0, header.1);
-        }
-    }
-
-    let response = request.send().await?;
-    Ok(response.text().await?)
-}
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use colored::Colorize;
+use reqwest::Client;
+use tokio::task::JoinSet;
+
+use crate::{
+    database::database::Database,
+    log,
+    parsers::{
+        detail::parse_detail,
+        pagination::{get_first_page_url, get_next_page_url},
+    },
+    structs::{Chapter, Target, TargetDetail},
+    Worker,
+};
+
+/// Spawns one thread for each Target,
+/// making a HTTP request and then parsing the contents.
+/// If there are new Chapters, saves them to the database.
+pub async fn dispatch_gofers(
+    database: Arc<dyn Database>,
+    targets: Vec<Target>,
+) -> (Worker, Result<()>) {
+    log!("{} Dispatching Gofers...", "[GOFR]".green());
+
+    let mut handles = JoinSet::new();
+
+    // Disabled targets are kept in the config but never fetched
+    for target in targets.into_iter().filter(|target| target.enabled) {
+        let cloned_db_ref = database.clone();
+        handles.spawn(run_gofer(cloned_db_ref, target.clone()));
+    }
+
+    while handles.join_next().await.is_some() {
+        // Loop until all handles have finished
+    }
+
+    log!("{} All Gofers have returned.", "[GOFR]".green());
+    (Worker::Gofer, Ok(()))
+}
+
+/// Child process of `dispatch_gofers`.
+/// This function gets run for every thread.
+pub async fn run_gofer(database: Arc<dyn Database>, target: Target) -> Result<()> {
+    log!("{} Gofer started for {}...", "[GOFR]".green(), target.name);
+    let mut chapters: Option<Vec<Chapter>> = None;
+
+    let mut attempts = 5;
+    while attempts > 0 {
+        match fetch_chapters(&target, Some(database.as_ref())).await {
+            Ok(fetched) => {
+                chapters = Some(fetched);
+                break;
+            }
+            Err(error) => {
+                log!(
+                    "{} Gofer for {} encountered an error: {}",
+                    "[GOFR]".green(),
+                    target.name,
+                    error
+                );
+            }
+        }
+
+        attempts -= 1;
+    }
+
+    if attempts == 0 {
+        log!(
+            "{} {}: Failed all fetching attempts.",
+            "[GOFR]".green(),
+            target.name,
+        );
+    }
+
+    if let Some(chapters_ref) = &chapters {
+        let mut attempts = 5;
+        while attempts > 0 {
+            let save = database.save_chapters(chapters_ref.as_slice()).await;
+            if save.is_ok() {
+                break;
+            }
+            attempts -= 1;
+        }
+
+        if attempts == 0 {
+            log!(
+                "{} {}: Failed saving chapters.",
+                "[GOFR]".green(),
+                target.name,
+            );
+        }
+    }
+
+    log!("{} {}: Gofer finished.", "[GOFR]".green(), target.name);
+
+    Ok(())
+}
+
+/// Makes a HTTP request to get the response body from a Target's `source`,
+/// and then parses the body using the defined `mode`.
+/// If the Target has `detail` rules, the page of each new chapter is fetched as well.
+/// Chapters are only checked against the saved ones when a `database` is given.
+pub async fn fetch_chapters(
+    target: &Target,
+    database: Option<&dyn Database>,
+) -> Result<Vec<Chapter>> {
+    let chapters = fetch_chapter_list(target, database).await?;
+
+    match &target.detail {
+        Some(detail) => fetch_details(target, detail, database, chapters).await,
+        None => Ok(chapters),
+    }
+}
+
+/// Fetches and parses the chapter list. Paginated sources are fetched page by page.
+async fn fetch_chapter_list(
+    target: &Target,
+    database: Option<&dyn Database>,
+) -> Result<Vec<Chapter>> {
+    let pagination = match &target.pagination {
+        Some(pagination) => pagination,
+        None => {
+            let body = fetch_body(&target.source, &target.request_headers).await?;
+            return parse_body(target, &body);
+        }
+    };
+
+    let mut pages: Vec<Vec<Chapter>> = vec![];
+    let mut visited = HashSet::new();
+    let mut url = get_first_page_url(&target.source, pagination)?;
+    loop {
+        visited.insert(url.clone());
+
+        let page = async {
+            let body = fetch_body(&url, &target.request_headers).await?;
+            let chapters = parse_body(target, &body)?;
+            anyhow::Ok((body, chapters))
+        };
+        let (body, chapters) = match page.await {
+            Ok(page) => page,
+            // Only the first page has to work, later ones may be past the end of the list
+            Err(error) if pages.is_empty() => return Err(error),
+            Err(error) => {
+                log!(
+                    "{} {}: Stopping at page {} ({}).",
+                    "[GOFR]".green(),
+                    target.name,
+                    pages.len() + 1,
+                    error
+                );
+                break;
+            }
+        };
+        if chapters.is_empty() {
+            break;
+        }
+
+        let all_known = match (pagination.stop_on_known, database) {
+            (true, Some(database)) => are_all_saved(database, target, &chapters).await?,
+            _ => false,
+        };
+        pages.push(chapters);
+        if all_known || pages.len() as u32 >= pagination.max_pages {
+            break;
+        }
+
+        let next = get_next_page_url(&target.source, pagination, pages.len() as u32, &url, &body)?;
+        match next {
+            Some(next) if !visited.contains(&next) => url = next,
+            _ => break,
+        }
+    }
+
+    // Every page is in ascending order already, so only the order of the pages is left
+    if !target.ascending_source {
+        pages.reverse();
+    }
+
+    Ok(pages.concat())
+}
+
+/// Checks whether every chapter of a page has already been saved.
+async fn are_all_saved(
+    database: &dyn Database,
+    target: &Target,
+    chapters: &[Chapter],
+) -> Result<bool> {
+    for chapter in chapters {
+        if !is_saved(database, target, chapter).await? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Checks whether a chapter has already been saved. Chapters of Targets with `detail` rules
+/// are matched by URL, since their saved title may be the one from the chapter's page.
+async fn is_saved(database: &dyn Database, target: &Target, chapter: &Chapter) -> Result<bool> {
+    match target.detail {
+        Some(_) => {
+            database
+                .is_chapter_url_saved(&chapter.manga, &chapter.url)
+                .await
+        }
+        None => database.is_chapter_saved(chapter).await,
+    }
+}
+
+/// Fetches the page of each new chapter (oldest first, up to the detail `limit`)
+/// and fills in the chapter with the values found there.
+/// With a `database`, saved chapters are dropped and new ones past the limit are left for the next run.
+async fn fetch_details(
+    target: &Target,
+    detail: &TargetDetail,
+    database: Option<&dyn Database>,
+    chapters: Vec<Chapter>,
+) -> Result<Vec<Chapter>> {
+    let mut detailed = vec![];
+    let mut requests = 0;
+    let mut deferred = 0;
+    for mut chapter in chapters {
+        let saved = match database {
+            Some(database) => is_saved(database, target, &chapter).await?,
+            None => false,
+        };
+        if saved {
+            continue;
+        }
+        if requests >= detail.limit {
+            match database {
+                Some(_) => deferred += 1,
+                None => detailed.push(chapter),
+            }
+            continue;
+        }
+
+        requests += 1;
+        let page = async {
+            let body = fetch_body(&chapter.url, &target.request_headers).await?;
+            parse_detail(target, detail, &mut chapter, &body)
+        };
+        // The chapter is still kept with the values from the list
+        if let Err(error) = page.await {
+            log!(
+                "{} {}: Could not get the details of {} ({}).",
+                "[GOFR]".green(),
+                target.name,
+                chapter.url,
+                error
+            );
+        }
+        detailed.push(chapter);
+    }
+
+    if deferred > 0 {
+        log!(
+            "{} {}: {} new chapters left for the next run.",
+            "[GOFR]".green(),
+            target.name,
+            deferred
+        );
+    }
+
+    Ok(detailed)
+}
+
+/// Parses a response body with the parser of the Target's `mode`.
+fn parse_body(target: &Target, body: &str) -> Result<Vec<Chapter>> {
+    target.mode.parser().parse(target, body)
+}
+
+/// Makes a HTTP request to get the response body from a given URL.
+/// Can optionally supply headers.
+async fn fetch_body(url: &str, headers: &Option<HashMap<String, String>>) -> Result<String> {
+    let client = Client::builder().gzip(true).brotli(true).build()?;
+    let mut request = client.get(url);
+    if headers.is_some() {
+        for header in headers.as_ref().unwrap() {
+            request = request.header(header.0, header.1);
+        }
+    }
+
+    let response = request.send().await?;
+    Ok(response.text().await?)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Duration, Utc};
+
+    use crate::{
+        database::{database::Database, sqlite::SqliteDatabase},
+        structs::{Chapter, DetailRules, Target, TargetDetail},
+    };
+
+    use super::fetch_details;
+
+    #[tokio::test]
+    async fn test_deferred_details_are_announced() {
+        let database = SqliteDatabase::new(":memory:").await;
+        database.set_feed_channel("1", "2").await.unwrap();
+
+        let detail = TargetDetail {
+            rules: DetailRules::Html {
+                title: None,
+                date: None,
+                date_format: None,
+                thumbnail: None,
+            },
+            limit: 1,
+        };
+        let target = Target {
+            detail: Some(detail.clone()),
+            ..Target::for_test("Test Manga", "https://comic-rss.com/feed.rss")
+        };
+        // Nothing listens there, so the chapters keep the values from the list
+        let chapter = |number: &str, date: DateTime<Utc>| Chapter {
+            manga: "Test Manga".into(),
+            number: number.into(),
+            title: format!("Chapter {}", number),
+            date,
+            url: format!("http://127.0.0.1:9/chapters/{}", number),
+            thumbnail: None,
+            logged_at: None,
+            announced_at: date,
+        };
+        let numbers = |chapters: Vec<Chapter>| {
+            chapters
+                .into_iter()
+                .map(|chapter| chapter.number)
+                .collect::<Vec<_>>()
+        };
+        let fetch = |chapters: Vec<Chapter>| async {
+            let chapters = fetch_details(&target, &detail, Some(&database), chapters)
+                .await
+                .unwrap();
+            database.save_chapters(&chapters).await.unwrap();
+            numbers(chapters)
+        };
+        let announce = || async {
+            let mangas = [String::from("Test Manga")];
+            let chapters = database
+                .get_unnanounced_chapters("1", &mangas)
+                .await
+                .unwrap();
+            database
+                .set_last_announced_time("1", &Utc::now())
+                .await
+                .unwrap();
+            numbers(chapters)
+        };
+
+        let old = Utc::now() - Duration::days(10);
+        assert_eq!(fetch(vec![chapter("1", old)]).await, ["1"]);
+        assert!(announce().await.is_empty());
+
+        // Only one of the two new chapters has its page fetched in a run
+        let date = Utc::now();
+        let chapters = vec![chapter("1", old), chapter("2", date), chapter("3", date)];
+        assert_eq!(fetch(chapters.clone()).await, ["2"]);
+        assert_eq!(announce().await, ["2"]);
+
+        // The other one is fetched in the next run, and announced although its date is older
+        assert_eq!(fetch(chapters).await, ["3"]);
+        assert_eq!(announce().await, ["3"]);
+    }
+}
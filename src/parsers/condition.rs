@@ -0,0 +1,210 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+use crate::structs::{DateOperand, JsonCondition, JsonDateTimeFormat, TargetKeys, ValueTest};
+
+use super::json::parse_date;
+
+/// What the conditions of a target are evaluated with, besides the chapter itself.
+struct Context<'a> {
+    date_format: &'a Option<JsonDateTimeFormat>,
+    timezone: &'a Option<Tz>,
+    now: DateTime<Utc>,
+}
+
+/// Checks whether a chapter is left out by the `skip` or `include` conditions of the keys.
+pub fn is_skipped(keys: &TargetKeys, chapter: &Value, timezone: &Option<Tz>) -> Result<bool> {
+    let context = Context {
+        date_format: &keys.date_format,
+        timezone,
+        now: Utc::now(),
+    };
+
+    for condition in &keys.skip {
+        if holds(condition, chapter, &context)? {
+            return Ok(true);
+        }
+    }
+    for condition in &keys.include {
+        if !holds(condition, chapter, &context)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn holds(condition: &JsonCondition, chapter: &Value, context: &Context) -> Result<bool> {
+    match condition {
+        JsonCondition::Any(conditions) => {
+            for condition in conditions {
+                if holds(condition, chapter, context)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        JsonCondition::All(conditions) => {
+            for condition in conditions {
+                if !holds(condition, chapter, context)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        JsonCondition::Value { path, test } => {
+            let value = path.get(chapter).filter(|value| !value.is_null());
+            Ok(passes(test, value, context))
+        }
+    }
+}
+
+/// Runs a test on a value of the chapter (`None` if it is missing or `null`).
+/// Values of the wrong type never pass, except for `NotEquals`.
+fn passes(test: &ValueTest, value: Option<&Value>, context: &Context) -> bool {
+    let value = match (test, value) {
+        (ValueTest::Exists, value) => return value.is_some(),
+        (ValueTest::Missing, value) => return value.is_none(),
+        (ValueTest::NotEquals(expected), value) => return value != Some(expected),
+        (_, None) => return false,
+        (_, Some(value)) => value,
+    };
+
+    match test {
+        ValueTest::Equals(expected) => value == expected,
+        ValueTest::Matches(regex) => match value {
+            Value::String(string) => regex.is_match(string),
+            Value::Number(_) | Value::Bool(_) => regex.is_match(&value.to_string()),
+            _ => false,
+        },
+        ValueTest::Compare {
+            ordering,
+            or_equal,
+            operand,
+        } => {
+            let number = match value {
+                Value::Number(number) => number.as_f64(),
+                Value::String(string) => string.trim().parse::<f64>().ok(),
+                _ => None,
+            };
+            match number.and_then(|number| number.partial_cmp(operand)) {
+                Some(Ordering::Equal) => *or_equal,
+                Some(compared) => compared == *ordering,
+                None => false,
+            }
+        }
+        ValueTest::Date {
+            after,
+            moment,
+            format,
+        } => {
+            let format = match format {
+                Some(_) => format,
+                None => context.date_format,
+            };
+            let moment = match moment {
+                DateOperand::Now => context.now,
+                DateOperand::At(moment) => *moment,
+            };
+            match parse_date(value, format, context.timezone) {
+                Ok(date) if *after => date > moment,
+                Ok(date) => date < moment,
+                Err(_) => false,
+            }
+        }
+        ValueTest::Exists | ValueTest::Missing | ValueTest::NotEquals(_) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use chrono::{Duration, Utc};
+    use regex::Regex;
+    use serde_json::json;
+
+    use crate::{
+        parsers::json_path::JsonPath,
+        structs::{DateOperand, JsonCondition, JsonDateTimeFormat, ValueTest},
+    };
+
+    use super::{holds, Context};
+
+    #[test]
+    fn test_holds() {
+        let now = Utc::now();
+        let context = Context {
+            date_format: &Some(JsonDateTimeFormat::UnixSec),
+            timezone: &None,
+            now,
+        };
+        let chapter = json!({
+            "state": "public",
+            "price": "30",
+            "rental": null,
+            "title": "第3話 (予告)",
+            "publish_start": (now + Duration::days(2)).timestamp(),
+        });
+        let value = |path: &str, test| JsonCondition::Value {
+            path: JsonPath::parse(path).unwrap(),
+            test,
+        };
+        let check = |condition: &JsonCondition| holds(condition, &chapter, &context).unwrap();
+
+        assert!(check(&value("state", ValueTest::Equals(json!("public")))));
+        assert!(!check(&value(
+            "state",
+            ValueTest::NotEquals(json!("public"))
+        )));
+        // Missing (and null) values are never equal
+        assert!(check(&value("missing", ValueTest::NotEquals(json!(1)))));
+        assert!(check(&value("rental", ValueTest::Missing)));
+        assert!(!check(&value("rental", ValueTest::Exists)));
+        assert!(check(&value(
+            "title",
+            ValueTest::Matches(Regex::new("予告").unwrap())
+        )));
+        let price = |ordering, or_equal, operand| {
+            value(
+                "price",
+                ValueTest::Compare {
+                    ordering,
+                    or_equal,
+                    operand,
+                },
+            )
+        };
+        assert!(check(&price(Ordering::Greater, false, 0.0)));
+        assert!(check(&price(Ordering::Less, true, 30.0)));
+        assert!(!check(&price(Ordering::Less, false, 30.0)));
+        // Published in the future
+        let publish_start = |after| {
+            value(
+                "publish_start",
+                ValueTest::Date {
+                    after,
+                    moment: DateOperand::Now,
+                    format: None,
+                },
+            )
+        };
+        assert!(check(&publish_start(true)));
+        assert!(!check(&publish_start(false)));
+
+        // Groups
+        let locked = JsonCondition::All(vec![
+            price(Ordering::Greater, false, 0.0),
+            value("rental", ValueTest::Exists),
+        ]);
+        assert!(!check(&locked));
+        assert!(check(&JsonCondition::Any(vec![
+            locked,
+            publish_start(true)
+        ])));
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::LazyLock;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+
+use super::utils::naive_to_utc;
+
+/// `dateFormat` value for dates written relative to now, like "3日前" or "2 hours ago".
+pub const RELATIVE_FORMAT: &str = "relative";
+
+/// Weekdays written after a date, like `(木)`, `（木曜日）` or `(Thu)`.
+static WEEKDAY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s*\((?:[日月火水木金土](?:曜日?)?|sun|mon|tues?|wed|thu(?:rs)?|fri|sat)\.?\)")
+        .unwrap()
+});
+
+/// Dates like `2023年11月2日`, `2023/11/02 10:30` or `11/2`.
+static ABSOLUTE_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:(\d{4})\s*[年/.\-]\s*)?(\d{1,2})\s*[月/.\-]\s*(\d{1,2})\s*日?(?:\s+|T)?(?:(\d{1,2})\s*[:時]\s*(\d{1,2})\s*分?(?:\s*:\s*(\d{1,2}))?)?$",
+    )
+    .unwrap()
+});
+
+/// English relative dates, like `2 hours ago` or `a day ago`.
+static RELATIVE_EN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(\d+|an?|one)\s*(sec|second|min|minute|hr|hour|day|week|month|year)s?\s+ago$")
+        .unwrap()
+});
+
+/// Japanese relative dates, like `3日前` or `2時間前`.
+static RELATIVE_JA: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d+)\s*(秒|分|時間|日|週間|週|[ヶかカヵケ箇]月|年)\s*前$").unwrap()
+});
+
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Turns full-width digits and punctuation into ASCII and removes a weekday written after the date.
+pub(super) fn normalize_date(text: &str) -> String {
+    let text: String = text
+        .trim()
+        .chars()
+        .map(|character| match character {
+            '０'..='９' | '：' | '／' | '（' | '）' | '－' | '．' => {
+                char::from_u32(character as u32 - 0xFEE0).unwrap_or(character)
+            }
+            '\u{3000}' => ' ',
+            character => character,
+        })
+        .collect();
+
+    WEEKDAY.replace_all(&text, "").trim().to_owned()
+}
+
+/// Parses a date like "3日前", "2 hours ago", "昨日" or "today", relative to `now`.
+/// Units of a day or more resolve to the start of that day in the target's time zone.
+/// Absolute dates (see `parse_absolute_date`) are accepted as well.
+pub fn parse_relative_date(
+    text: &str,
+    now: DateTime<Utc>,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    let text = normalize_date(text);
+    let (amount, unit) = match text.to_lowercase().as_str() {
+        "now" | "just now" | "たった今" | "今" => return Ok(now),
+        "today" | "今日" | "本日" => (0, Unit::Day),
+        "yesterday" | "昨日" => (1, Unit::Day),
+        "一昨日" | "おととい" => (2, Unit::Day),
+        lowercase => match read_relative(lowercase) {
+            Some(relative) => relative,
+            None => {
+                return parse_absolute_date(&text, now, timezone)
+                    .ok_or(anyhow!("Could not parse relative date {}", text))
+            }
+        },
+    };
+
+    let out_of_range = || anyhow!("Relative date {} is out of range", text);
+    let before_now = |duration: Option<Duration>| {
+        duration
+            .and_then(|duration| now.checked_sub_signed(duration))
+            .ok_or_else(out_of_range)
+    };
+
+    let today = local_date(now, timezone);
+    let months = u32::try_from(amount).ok();
+    let day = match unit {
+        Unit::Second => return before_now(Duration::try_seconds(amount)),
+        Unit::Minute => return before_now(Duration::try_minutes(amount)),
+        Unit::Hour => return before_now(Duration::try_hours(amount)),
+        Unit::Day => Duration::try_days(amount).and_then(|days| today.checked_sub_signed(days)),
+        Unit::Week => Duration::try_weeks(amount).and_then(|weeks| today.checked_sub_signed(weeks)),
+        Unit::Month => months.and_then(|months| today.checked_sub_months(Months::new(months))),
+        Unit::Year => months
+            .and_then(|years| years.checked_mul(12))
+            .and_then(|months| today.checked_sub_months(Months::new(months))),
+    };
+    let day = day.ok_or_else(out_of_range)?;
+
+    naive_to_utc(&day.and_time(NaiveTime::MIN), timezone)
+}
+
+/// Reads the amount and unit of "2 hours ago" or "2時間前".
+fn read_relative(text: &str) -> Option<(i64, Unit)> {
+    if let Some(captures) = RELATIVE_EN.captures(text) {
+        let amount = match &captures[1] {
+            "a" | "an" | "one" => 1,
+            amount => amount.parse().ok()?,
+        };
+        let unit = match &captures[2] {
+            "sec" | "second" => Unit::Second,
+            "min" | "minute" => Unit::Minute,
+            "hr" | "hour" => Unit::Hour,
+            "day" => Unit::Day,
+            "week" => Unit::Week,
+            "month" => Unit::Month,
+            _ => Unit::Year,
+        };
+        return Some((amount, unit));
+    }
+
+    let captures = RELATIVE_JA.captures(text)?;
+    let unit = match &captures[2] {
+        "秒" => Unit::Second,
+        "分" => Unit::Minute,
+        "時間" => Unit::Hour,
+        "日" => Unit::Day,
+        "週間" | "週" => Unit::Week,
+        "年" => Unit::Year,
+        _ => Unit::Month,
+    };
+    Some((captures[1].parse().ok()?, unit))
+}
+
+/// Parses dates like `2023年11月2日`, `2023/11/02 10:30` or `11/2(木)` in the target's time zone.
+/// Dates without a year are in the year of `now`, or the year before if that would be in the future.
+pub fn parse_absolute_date(
+    text: &str,
+    now: DateTime<Utc>,
+    timezone: &Option<Tz>,
+) -> Option<DateTime<Utc>> {
+    let text = normalize_date(text);
+    let captures = ABSOLUTE_DATE.captures(&text)?;
+    let number = |index: usize| -> Option<u32> {
+        captures
+            .get(index)
+            .and_then(|number| number.as_str().parse().ok())
+    };
+
+    let (month, day) = (number(2)?, number(3)?);
+    let time = match number(4) {
+        Some(hour) => NaiveTime::from_hms_opt(hour, number(5)?, number(6).unwrap_or(0))?,
+        None => NaiveTime::MIN,
+    };
+    let datetime = |year: i32| -> Option<NaiveDateTime> {
+        Some(NaiveDate::from_ymd_opt(year, month, day)?.and_time(time))
+    };
+
+    let datetime = match number(1) {
+        Some(year) => datetime(year as i32)?,
+        None => {
+            let today = local_date(now, timezone);
+            let this_year = datetime(today.year());
+            match this_year {
+                Some(datetime) if datetime.date() <= today + Duration::days(1) => datetime,
+                _ => datetime(today.year() - 1)?,
+            }
+        }
+    };
+
+    naive_to_utc(&datetime, timezone).ok()
+}
+
+/// The date of `now` in the target's time zone.
+fn local_date(now: DateTime<Utc>, timezone: &Option<Tz>) -> NaiveDate {
+    match timezone {
+        Some(timezone) => now.with_timezone(timezone).date_naive(),
+        None => now.date_naive(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::DateTime;
+
+    use super::{parse_absolute_date, parse_relative_date};
+
+    #[test]
+    fn test_parse_relative_date() {
+        let now = DateTime::parse_from_rfc3339("2023-11-02T03:00:00+00:00")
+            .unwrap()
+            .into();
+        let tokyo = Some(chrono_tz::Asia::Tokyo);
+        let parse = |text: &str| parse_relative_date(text, now, &tokyo).unwrap().to_rfc3339();
+
+        assert_eq!(parse("2 hours ago"), "2023-11-02T01:00:00+00:00");
+        assert_eq!(parse("5分前"), "2023-11-02T02:55:00+00:00");
+        assert_eq!(parse("たった今"), "2023-11-02T03:00:00+00:00");
+        // Days start at midnight in Tokyo
+        assert_eq!(parse("今日"), "2023-11-01T15:00:00+00:00");
+        assert_eq!(parse("Yesterday"), "2023-10-31T15:00:00+00:00");
+        assert_eq!(parse("３日前"), "2023-10-29T15:00:00+00:00");
+        assert_eq!(parse("a week ago"), "2023-10-25T15:00:00+00:00");
+        assert_eq!(parse("1ヶ月前"), "2023-10-01T15:00:00+00:00");
+        // Absolute dates are accepted too
+        assert_eq!(parse("2023年10月5日"), "2023-10-04T15:00:00+00:00");
+        assert!(parse_relative_date("soon", now, &tokyo).is_err());
+        // Out of range amounts are errors, not panics
+        for text in [
+            "99999999999999日前",
+            "9999999999999 hours ago",
+            "999999999年前",
+        ] {
+            assert!(parse_relative_date(text, now, &tokyo).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_absolute_date() {
+        let now = DateTime::parse_from_rfc3339("2024-01-05T03:00:00+00:00")
+            .unwrap()
+            .into();
+        let parse = |text: &str| {
+            parse_absolute_date(text, now, &None)
+                .map(|date| date.to_rfc3339())
+                .unwrap_or_default()
+        };
+
+        assert_eq!(parse("2023年11月2日"), "2023-11-02T00:00:00+00:00");
+        assert_eq!(parse("2023年11月2日 10時30分"), "2023-11-02T10:30:00+00:00");
+        assert_eq!(parse("2023/11/02(木) 10:30"), "2023-11-02T10:30:00+00:00");
+        assert_eq!(parse("2023.11.02（木曜日）"), "2023-11-02T00:00:00+00:00");
+        // Without a year, a date after today is from last year
+        assert_eq!(parse("1/4(木)"), "2024-01-04T00:00:00+00:00");
+        assert_eq!(parse("11/2(木)"), "2023-11-02T00:00:00+00:00");
+        assert_eq!(parse("Nov 2"), "");
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use chrono::Duration;
+use scraper::Html;
+use serde_json::Value;
+
+use crate::structs::{Chapter, DetailRules, HtmlField, Target, TargetDetail};
+
+use super::{
+    html::{get_value, parse_string_to_datetime},
+    json::{convert_value_into_string, get_path, parse_date},
+    json_path::JsonPath,
+    utils::make_image_link,
+};
+
+/// Fills in the values of a chapter with the ones found on its own page.
+pub fn parse_detail(
+    target: &Target,
+    detail: &TargetDetail,
+    chapter: &mut Chapter,
+    source: &str,
+) -> Result<()> {
+    let (title, date, thumbnail) = match &detail.rules {
+        DetailRules::Html {
+            title,
+            date,
+            date_format,
+            thumbnail,
+        } => {
+            let html = Html::parse_document(source);
+            let root = html.root_element();
+            let get = |field: &Option<HtmlField>| {
+                field
+                    .as_ref()
+                    .and_then(|field| get_value(&root, &field.tag, &field.attribute).ok())
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty())
+            };
+
+            let date = match get(date) {
+                Some(date) => Some(parse_string_to_datetime(
+                    &date,
+                    date_format,
+                    &target.timezone,
+                )?),
+                None => None,
+            };
+            (get(title), date, get(thumbnail))
+        }
+        DetailRules::Json {
+            title,
+            date,
+            date_format,
+            thumbnail,
+        } => {
+            let json: Value = serde_json::from_str(source)?;
+            let get_value = |path: &Option<JsonPath>| {
+                path.as_ref()
+                    .and_then(|path| get_path(&json, path).ok())
+                    .filter(|value| !value.is_null())
+            };
+            let get = |path: &Option<JsonPath>| {
+                get_value(path)
+                    .and_then(|value| convert_value_into_string(value).ok())
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty())
+            };
+
+            let date = match get_value(date) {
+                Some(date) => Some(parse_date(date, date_format, &target.timezone)?),
+                None => None,
+            };
+            (get(title), date, get(thumbnail))
+        }
+    };
+
+    if let Some(title) = title {
+        chapter.title = title;
+    }
+    if let Some(date) = date {
+        chapter.date = date;
+        chapter.announced_at = date + Duration::days(target.delay.unwrap_or(0).into());
+    }
+    if let Some(thumbnail) = thumbnail {
+        // Relative image URLs are relative to the chapter's page
+        let page_url = Some(chapter.url.to_owned());
+        chapter.thumbnail = make_image_link(&page_url, &thumbnail).or(chapter.thumbnail.take());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Utc};
+
+    use crate::{
+        parsers::{html::HtmlParser, json_path::JsonPath},
+        structs::{
+            Chapter, DetailRules, HtmlField, JsonDateTimeFormat, ParseMode, Target, TargetDetail,
+        },
+    };
+
+    use super::parse_detail;
+
+    #[test]
+    fn test_parse_detail() {
+        let target = Target {
+            mode: ParseMode(&HtmlParser),
+            delay: Some(1),
+            ..Target::for_test("Test Manga", "https://comic-html.com/series/1")
+        };
+        let mut detail = TargetDetail {
+            rules: DetailRules::Html {
+                title: Some(HtmlField {
+                    tag: Some("h1.episode-title".into()),
+                    attribute: None,
+                }),
+                date: Some(HtmlField {
+                    tag: Some("time".into()),
+                    attribute: Some("datetime".into()),
+                }),
+                date_format: Some("%Y-%m-%d %H:%M".into()),
+                thumbnail: Some(HtmlField {
+                    tag: Some("img.cover".into()),
+                    attribute: Some("src".into()),
+                }),
+            },
+            limit: 10,
+        };
+        let now = Utc::now();
+        let mut chapter = Chapter {
+            manga: "Test Manga".into(),
+            number: "12".into(),
+            title: "第12話".into(),
+            date: now,
+            url: "https://comic-html.com/episode/12".into(),
+            thumbnail: None,
+            logged_at: None,
+            announced_at: now,
+        };
+
+        let source = r###"<html><body>
+            <h1 class="episode-title">
+                第12話 再会
+            </h1>
+            <time datetime="2023-11-02 10:00">11月2日</time>
+            <img class="cover" src="/images/12.jpg" />
+        </body></html>"###;
+        parse_detail(&target, &detail, &mut chapter, source).unwrap();
+        assert_eq!(chapter.title, "第12話 再会");
+        assert_eq!(
+            chapter.date,
+            DateTime::parse_from_rfc3339("2023-11-02T10:00:00+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapter.announced_at,
+            DateTime::parse_from_rfc3339("2023-11-03T10:00:00+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapter.thumbnail.as_deref(),
+            Some("https://comic-html.com/images/12.jpg"),
+        );
+
+        // Values that are missing from the page are kept
+        detail.rules = DetailRules::Json {
+            title: Some(JsonPath::parse("episode.title").unwrap()),
+            date: Some(JsonPath::parse("episode.published_at").unwrap()),
+            date_format: Some(JsonDateTimeFormat::UnixSec),
+            thumbnail: None,
+        };
+        let source = r###"{ "episode": { "title": null, "published_at": 1699000000 } }"###;
+        parse_detail(&target, &detail, &mut chapter, source).unwrap();
+        assert_eq!(chapter.title, "第12話 再会");
+        assert_eq!(
+            chapter.date,
+            DateTime::parse_from_rfc3339("2023-11-03T08:26:40+00:00").unwrap(),
+        );
+    }
+}
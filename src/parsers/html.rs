@@ -1,242 +1,810 @@
-use std::str::FromStr;
-
-use anyhow::{anyhow, bail, Result};
-use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
-use scraper::{ElementRef, Html, Selector};
-
-use crate::structs::{Chapter, Target};
-
-use super::utils::make_link;
-
-pub(super) fn make_selector(string: &str) -> Result<Selector> {
-    let selector = Selector::parse(string);
-    if selector.is_err() {
-        bail!("Failed creating selector: {}", string);
-    }
-
-    Ok(selector.unwrap())
-}
-
-fn get_sub_element<'a>(element: &'a ElementRef, tag: &Option<String>) -> Result<ElementRef<'a>> {
-    match tag {
-        Some(tag) => {
-            let selector = make_selector(tag.as_str())?;
-            let first_element_result = element.select(&selector).next();
-            let unwrapped =
-                first_element_result.ok_or(anyhow!("No element found using tag {}", tag))?;
-            Ok(unwrapped)
-        }
-        None => Ok(element.clone()),
-    }
-}
-
-pub(super) fn get_value(
-    element: &ElementRef,
-    tag: &Option<String>,
-    attribute: &Option<String>,
-) -> Result<String> {
-    let element = get_sub_element(element, tag)?;
-    match attribute {
-        Some(attribute) => {
-            let attribute_text = element.value().attr(attribute.as_str());
-            let attribute_text =
-                attribute_text.ok_or(anyhow!("No attribute {} found in tag", attribute))?;
-            Ok(String::from(attribute_text))
-        }
-        None => {
-            let text = element.text().collect::<String>();
-            Ok(text)
-        }
-    }
-}
-
-fn parse_string_to_datetime(date_string: &str, format: &Option<String>) -> Result<NaiveDateTime> {
-    if date_string.contains(":") {
-        let datetime = match format {
-            Some(format) => NaiveDateTime::parse_from_str(&date_string, format.as_str()),
-            None => NaiveDateTime::from_str(&date_string),
-        }?;
-        Ok(datetime)
-    } else {
-        let date = match format {
-            Some(format) => NaiveDate::parse_from_str(&date_string, format.as_str()),
-            None => NaiveDate::from_str(&date_string),
-        }?;
-        Ok(date.and_hms_opt(0, 0, 0).unwrap())
-    }
-}
-
-pub fn parse_html(target: &Target, source: &str) -> Result<Vec<Chapter>> {
-    let mut chapters: Vec<Chapter> = vec![];
-    let html = Html::parse_document(source);
-    let tags = target.tags.as_ref().unwrap();
-
-    let selector = make_selector(&tags.chapters_tag)?;
-
-    for element in html.select(&selector) {
-        let assemble_chapter = || -> Result<Chapter> {
-            let number = get_value(&element, &tags.number_tag, &tags.number_attribute)?;
-
-            let title = get_value(&element, &tags.title_tag, &tags.title_attribute)?;
-
-            let get_date: Result<DateTime<Utc>> = 'setdate: {
-                if tags.date_tag.is_none() && tags.date_attribute.is_none() {
-                    let now = Utc::now();
-                    break 'setdate Ok(now);
-                }
-
-                let date_string = get_value(&element, &tags.date_tag, &tags.date_attribute)?;
-                let naive_datetime = parse_string_to_datetime(&date_string, &tags.date_format)?;
-
-                Ok(naive_datetime.and_utc())
-            };
-            let date = get_date?;
-
-            let url = get_value(&element, &tags.url_tag, &tags.url_attribute)?;
-
-            Ok(Chapter {
-                manga: target.name.to_owned(),
-                number: number,
-                title: title,
-                date: date,
-                url: match &target.base_url {
-                    Some(base_url) => make_link(&base_url, &url),
-                    None => url,
-                },
-                logged_at: None,
-                announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
-            })
-        };
-
-        let chapter = assemble_chapter();
-        if chapter.is_err() {
-            continue;
-        }
-
-        chapters.push(chapter.unwrap())
-    }
-
-    if !target.ascending_source {
-        chapters.reverse();
-    }
-
-    Ok(chapters)
-}
-
-#[cfg(test)]
-mod test {
-    use chrono::DateTime;
-
-    use crate::structs::{ParseMode, Target, TargetTags};
-
-    use super::parse_html;
-
-    #[test]
-    fn test_parse_html() {
-        let target = Target {
-            name: "Test Manga".into(),
-            source: "https://comic-html.com/test.html".into(),
-            ascending_source: false,
-            mode: ParseMode::Html,
-            base_url: Some("https://comic-html.com".into()),
-            request_headers: None,
-            delay: Some(7),
-            keys: None,
-            tags: Some(TargetTags {
-                chapters_tag: "div#chapterlist li".into(),
-                number_tag: None,
-                number_attribute: Some("data-num".into()),
-                title_tag: Some("div div a span.chapternum".into()),
-                title_attribute: None,
-                date_tag: Some("div div a span.chapterdate".into()),
-                date_attribute: None,
-                date_format: Some("%B %-d, %Y".into()),
-                url_tag: Some("div div a".into()),
-                url_attribute: Some("href".into()),
-            }),
-        };
-
-        let source = r###"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "https://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">
-        <html xmlns="https://www.w3.org/1999/xhtml" lang="ja">
-        <head>
-            <title>Test Manga</title>
-        </head>
-        <body>
-            <div id="content">
-                <div class="wrapper">
-                    <article>
-                        <div class="eplister" id="chapterlist">
-                            <ul class="clstyle">
-                                <li data-num="51">
-                                    <div class="chbox">
-                                        <div class="eph-num">
-                                            <a href="https://comic-html.com/chapter/51">
-                                                <span class="chapternum">Chapter 51</span>
-                                                <span class="chapterdate">June 3, 2022</span>
-                                            </a>
-                                        </div>
-                                    </div>
-                                </li>
-                                <li data-num="50">
-                                    <div class="chbox">
-                                        <div class="eph-num">
-                                            <a>
-                                                <span class="chapternum">Chapter Breaker</span>
-                                                <span class="chapterdate">May 29, 2022</span>
-                                            </a>
-                                        </div>
-                                    </div>
-                                </li>
-                                <li data-num="50">
-                                    <div class="chbox">
-                                        <div class="eph-num">
-                                            <a href="https://comic-html.com/chapter/50">
-                                                <span class="chapternum">Chapter 50</span>
-                                                <span class="chapterdate">May 15, 2022</span>
-                                            </a>
-                                        </div>
-                                    </div>
-                                </li>
-                            </ul>
-                        </div>
-                    </article>
-                </div>
-            </div>
-        </body>
-        </html>"###;
-        let chapters = parse_html(&target, source).unwrap();
-
-        // Should have 2 chapters
-        assert!(chapters.len() == 2);
-        // Check manga title
-        assert_eq!(chapters[0].manga, "Test Manga");
-        assert_eq!(chapters[0].manga, chapters[1].manga);
-        // Check numbers (chapter IDs)
-        assert_eq!(chapters[0].number, "50");
-        assert_eq!(chapters[1].number, "51");
-        // Check titles
-        assert_eq!(chapters[0].title, "Chapter 50");
-        assert_eq!(chapters[1].title, "Chapter 51");
-        // Check links
-        assert_eq!(chapters[0].url, "https://comic-html.com/chapter/50");
-        assert_eq!(chapters[1].url, "https://comic-html.com/chapter/51");
-        // Check dates
-        assert_eq!(
-            chapters[0].date,
-            DateTime::parse_from_rfc3339("2022-05-15T00:00:00.000+00:00").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].date,
-            DateTime::parse_from_rfc3339("2022-06-03T00:00:00.000+00:00").unwrap(),
-        );
-        // Check announce time
-        assert_eq!(
-            chapters[0].announced_at,
-            DateTime::parse_from_rfc3339("2022-05-22T00:00:00.000+00:00").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].announced_at,
-            DateTime::parse_from_rfc3339("2022-06-10T00:00:00.000+00:00").unwrap(),
-        );
-    }
-}
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{json, Map, Value as JsonValue};
+use toml::Value as TomlValue;
+
+use crate::{
+    config::reader::TableReader,
+    structs::{
+        Chapter, HtmlField, HtmlSkipRule, ParseRules, ScriptLocator, SkipCondition, Target,
+        TargetTags,
+    },
+};
+
+use super::{
+    dates::{normalize_date, parse_absolute_date, parse_relative_date, RELATIVE_FORMAT},
+    parser::Parser,
+    transform::apply_transforms,
+    utils::{make_image_link, make_link, naive_to_utc},
+};
+
+/// Keys allowed in the `tags` table of a target.
+pub const TARGET_TAGS_KEYS: [&str; 16] = [
+    "chaptersTag",
+    "numberTag",
+    "numberAttribute",
+    "titleTag",
+    "titleAttribute",
+    "dateTag",
+    "dateAttribute",
+    "dateFormat",
+    "urlTag",
+    "urlAttribute",
+    "thumbnailTag",
+    "thumbnailAttribute",
+    "scriptMarker",
+    "scriptPattern",
+    "skip",
+    "fields",
+];
+
+/// Keys allowed in each rule of `tags.skip`.
+pub const TARGET_SKIP_RULE_KEYS: [&str; 4] = ["tag", "attribute", "equals", "matches"];
+
+pub(super) fn make_selector(string: &str) -> Result<Selector> {
+    let selector = Selector::parse(string);
+    if selector.is_err() {
+        bail!("Failed creating selector: {}", string);
+    }
+
+    Ok(selector.unwrap())
+}
+
+fn get_sub_element<'a>(element: &'a ElementRef, tag: &Option<String>) -> Result<ElementRef<'a>> {
+    match tag {
+        Some(tag) => {
+            let selector = make_selector(tag.as_str())?;
+            let first_element_result = element.select(&selector).next();
+            let unwrapped =
+                first_element_result.ok_or(anyhow!("No element found using tag {}", tag))?;
+            Ok(unwrapped)
+        }
+        None => Ok(*element),
+    }
+}
+
+pub(super) fn get_value(
+    element: &ElementRef,
+    tag: &Option<String>,
+    attribute: &Option<String>,
+) -> Result<String> {
+    let element = get_sub_element(element, tag)?;
+    match attribute {
+        Some(attribute) => {
+            let attribute_text = element.value().attr(attribute.as_str());
+            let attribute_text =
+                attribute_text.ok_or(anyhow!("No attribute {} found in tag", attribute))?;
+            Ok(String::from(attribute_text))
+        }
+        None => {
+            let text = element.text().collect::<String>();
+            Ok(text)
+        }
+    }
+}
+
+/// Parses a date, in the target's time zone unless the date has its own offset.
+/// Without a format, Japanese dates and dates followed by a weekday (`2023年11月2日`, `11/2(木)`) are read as well.
+pub(super) fn parse_string_to_datetime(
+    date_string: &str,
+    format: &Option<String>,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    if format.as_deref() == Some(RELATIVE_FORMAT) {
+        return parse_relative_date(date_string, Utc::now(), timezone);
+    }
+
+    let with_offset = match format {
+        Some(format) => DateTime::parse_from_str(date_string, format.as_str()),
+        None => DateTime::parse_from_rfc3339(date_string),
+    };
+    if let Ok(datetime) = with_offset {
+        return Ok(datetime.into());
+    }
+
+    match parse_string_to_naive_datetime(date_string, format) {
+        Ok(datetime) => naive_to_utc(&datetime, timezone),
+        Err(error) => match format {
+            // The weekday after a date (`(木)`) can't be written in a format
+            Some(_) => match parse_string_to_naive_datetime(&normalize_date(date_string), format) {
+                Ok(datetime) => naive_to_utc(&datetime, timezone),
+                Err(_) => Err(error),
+            },
+            None => parse_absolute_date(date_string, Utc::now(), timezone).ok_or(error),
+        },
+    }
+}
+
+fn parse_string_to_naive_datetime(
+    date_string: &str,
+    format: &Option<String>,
+) -> Result<NaiveDateTime> {
+    if date_string.contains(":") {
+        let datetime = match format {
+            Some(format) => NaiveDateTime::parse_from_str(date_string, format.as_str()),
+            None => NaiveDateTime::from_str(date_string),
+        }?;
+        Ok(datetime)
+    } else {
+        let date = match format {
+            Some(format) => NaiveDate::parse_from_str(date_string, format.as_str()),
+            None => NaiveDate::from_str(date_string),
+        }?;
+        Ok(date.and_hms_opt(0, 0, 0).unwrap())
+    }
+}
+
+/// Checks whether a chapter element matches any of the target's skip rules.
+fn is_skipped(element: &ElementRef, rules: &[HtmlSkipRule]) -> bool {
+    for rule in rules {
+        let found = match &rule.tag {
+            Some(selector) => match selector.matches(element) {
+                true => Some(*element),
+                false => element.select(selector).next(),
+            },
+            None => Some(*element),
+        };
+        let found = match found {
+            Some(found) => found,
+            None => continue,
+        };
+
+        let value = match &rule.attribute {
+            Some(attribute) => match found.value().attr(attribute) {
+                Some(value) => value.to_owned(),
+                None => continue,
+            },
+            None => found.text().collect::<String>(),
+        };
+        let skipped = match &rule.condition {
+            Some(SkipCondition::Equals(expected)) => value.trim() == expected,
+            Some(SkipCondition::Matches(regex)) => regex.is_match(&value),
+            None => true,
+        };
+        if skipped {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Gets the `tags` of a `html` target.
+pub fn tags(rules: &ParseRules) -> Result<&TargetTags> {
+    rules.get().ok_or(anyhow!("Target has no tags"))
+}
+
+pub fn parse_html(target: &Target, source: &str) -> Result<Vec<Chapter>> {
+    let mut chapters: Vec<Chapter> = vec![];
+    let html = Html::parse_document(source);
+    let tags = tags(&target.rules)?;
+
+    let selector = make_selector(&tags.chapters_tag)?;
+
+    for element in html.select(&selector) {
+        if is_skipped(&element, &tags.skip) {
+            continue;
+        }
+
+        let assemble_chapter = || -> Result<Chapter> {
+            let number = get_value(&element, &tags.number_tag, &tags.number_attribute)?;
+
+            let title = match &target.title_template {
+                Some(template) => {
+                    let title = get_value(&element, &tags.title_tag, &tags.title_attribute).ok();
+                    template.render(|name| match name {
+                        "number" => Some(number.to_owned()),
+                        "title" => title.to_owned(),
+                        name => tags.fields.get(name).and_then(|field| {
+                            get_value(&element, &field.tag, &field.attribute).ok()
+                        }),
+                    })
+                }
+                None => get_value(&element, &tags.title_tag, &tags.title_attribute)?,
+            };
+
+            let get_date: Result<DateTime<Utc>> = 'setdate: {
+                if tags.date_tag.is_none() && tags.date_attribute.is_none() {
+                    let now = Utc::now();
+                    break 'setdate Ok(now);
+                }
+
+                let date_string = get_value(&element, &tags.date_tag, &tags.date_attribute)?;
+                parse_string_to_datetime(&date_string, &tags.date_format, &target.timezone)
+            };
+            let date = get_date?;
+
+            let url = get_value(&element, &tags.url_tag, &tags.url_attribute)?;
+
+            let thumbnail = match tags.thumbnail_tag.is_none() && tags.thumbnail_attribute.is_none()
+            {
+                true => None,
+                false => get_value(&element, &tags.thumbnail_tag, &tags.thumbnail_attribute)
+                    .ok()
+                    .and_then(|link| make_image_link(&target.base_url, link.trim())),
+            };
+
+            Ok(Chapter {
+                manga: target.name.to_owned(),
+                number,
+                title,
+                date,
+                url: match &target.base_url {
+                    Some(base_url) => make_link(base_url, &url),
+                    None => url,
+                },
+                thumbnail,
+                logged_at: None,
+                announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
+            })
+        };
+
+        let chapter = assemble_chapter();
+        if chapter.is_err() {
+            continue;
+        }
+
+        chapters.push(chapter.unwrap())
+    }
+
+    apply_transforms(target, &mut chapters);
+
+    if !target.ascending_source {
+        chapters.reverse();
+    }
+
+    Ok(chapters)
+}
+
+/// Parser of `mode = "html"`, reading the chapters with the CSS selectors of its `tags` table.
+pub struct HtmlParser;
+
+impl Parser for HtmlParser {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn tables(&self) -> &'static [&'static str] {
+        &["tags"]
+    }
+
+    fn table_schema(&self, _table: &str) -> JsonValue {
+        tags_schema()
+    }
+
+    fn read_rules(&self, target: &TableReader, _has_template: bool) -> Option<ParseRules> {
+        let tags = read_tags(&target.table("tags")?, self.name())?;
+        Some(ParseRules::new(tags))
+    }
+
+    fn page_strategies(&self) -> &'static [&'static str] {
+        &["nextTag"]
+    }
+
+    fn has_field(&self, rules: &ParseRules, name: &str) -> bool {
+        tags(rules).is_ok_and(|tags| tags.fields.contains_key(name))
+    }
+
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>> {
+        parse_html(target, body)
+    }
+}
+
+/// Reads the "parse tags" for a target that has an HTML source.
+pub fn read_tags(reader: &TableReader, mode: &str) -> Option<TargetTags> {
+    reader.deny_unknown_keys(&TARGET_TAGS_KEYS);
+
+    let chapters_tag = reader.required_string("chaptersTag");
+    let number_tag = reader.non_empty_string("numberTag");
+    let number_attribute = reader.non_empty_string("numberAttribute");
+    let title_tag = reader.non_empty_string("titleTag");
+    let title_attribute = reader.non_empty_string("titleAttribute");
+    let date_tag = reader.non_empty_string("dateTag");
+    let date_attribute = reader.non_empty_string("dateAttribute");
+    let date_format = reader.non_empty_string("dateFormat");
+    let url_tag = reader.non_empty_string("urlTag");
+    let url_attribute = reader.non_empty_string("urlAttribute");
+    let thumbnail_tag = reader.non_empty_string("thumbnailTag");
+    let thumbnail_attribute = reader.non_empty_string("thumbnailAttribute");
+    let script = read_script_locator(reader, mode);
+    let skip = read_html_skip_rules(reader, mode);
+    let fields = read_html_fields(reader);
+
+    Some(TargetTags {
+        chapters_tag: chapters_tag?,
+        number_tag,
+        number_attribute,
+        title_tag,
+        title_attribute,
+        date_tag,
+        date_attribute,
+        date_format,
+        url_tag,
+        url_attribute,
+        thumbnail_tag,
+        thumbnail_attribute,
+        script: script?,
+        skip: skip?,
+        fields: fields?,
+    })
+}
+
+/// Reads the rules for skipping chapter elements of a HTML target, e.g.
+/// `skip = [{ tag = ".is-locked" }, { tag = "span.badge", matches = "有料|Coming soon" }]`.
+/// Returns `None` if any rule is invalid.
+fn read_html_skip_rules(reader: &TableReader, mode: &str) -> Option<Vec<HtmlSkipRule>> {
+    let array = match reader.get("skip") {
+        Some(TomlValue::Array(array)) => array,
+        Some(_) => {
+            reader.error("skip", "must be an array of rules");
+            return None;
+        }
+        None => return Some(vec![]),
+    };
+    if mode != "html" {
+        reader.error("skip", "is only supported by mode \"html\"");
+    }
+
+    let mut rules = vec![];
+    let mut valid = true;
+    for (index, item) in array.iter().enumerate() {
+        match read_html_skip_rule(item) {
+            Ok(rule) => rules.push(rule),
+            Err(message) => {
+                reader.error(&format!("skip[{}]", index), message);
+                valid = false;
+            }
+        }
+    }
+
+    valid.then_some(rules)
+}
+
+fn read_html_skip_rule(item: &TomlValue) -> Result<HtmlSkipRule, String> {
+    let table = item.as_table().ok_or(String::from("must be a table"))?;
+    let string = |key: &str| -> Result<Option<String>, String> {
+        match table.get(key) {
+            Some(TomlValue::String(string)) if string.is_empty() => {
+                Err(format!("{} must not be empty", key))
+            }
+            Some(TomlValue::String(string)) => Ok(Some(string.to_owned())),
+            Some(_) => Err(format!("{} must be a string", key)),
+            None => Ok(None),
+        }
+    };
+
+    if let Some(key) = table
+        .keys()
+        .find(|key| !TARGET_SKIP_RULE_KEYS.contains(&key.as_str()))
+    {
+        return Err(format!("{} is not a known key", key));
+    }
+
+    let tag = match string("tag")? {
+        Some(tag) => match Selector::parse(&tag) {
+            Ok(selector) => Some(selector),
+            Err(_) => return Err(format!("tag is not a valid selector ({})", tag)),
+        },
+        None => None,
+    };
+    let attribute = string("attribute")?;
+    let condition = match (string("equals")?, string("matches")?) {
+        (Some(_), Some(_)) => return Err(String::from("cannot have both equals and matches")),
+        (Some(value), None) => Some(SkipCondition::Equals(value)),
+        (None, Some(pattern)) => match Regex::new(&pattern) {
+            Ok(regex) => Some(SkipCondition::Matches(regex)),
+            Err(error) => return Err(format!("matches is not a valid regex ({})", error)),
+        },
+        (None, None) => None,
+    };
+    if tag.is_none() && attribute.is_none() && condition.is_none() {
+        return Err(String::from(
+            "must have at least one of tag, attribute, equals or matches",
+        ));
+    }
+
+    Ok(HtmlSkipRule {
+        tag,
+        attribute,
+        condition,
+    })
+}
+
+/// Reads where the JSON starts inside the script of a `json_in_html` target,
+/// from either `scriptMarker` or `scriptPattern`.
+fn read_script_locator(reader: &TableReader, mode: &str) -> Option<Option<ScriptLocator>> {
+    let key = match (reader.has("scriptMarker"), reader.has("scriptPattern")) {
+        (false, false) => return Some(None),
+        (true, true) => {
+            reader.error("scriptPattern", "cannot be used together with scriptMarker");
+            return None;
+        }
+        (true, false) => "scriptMarker",
+        (false, true) => "scriptPattern",
+    };
+    if mode != "json_in_html" {
+        reader.error(key, "is only supported by mode \"json_in_html\"");
+    }
+
+    let value = reader.string(key).filter(|value| {
+        if value.is_empty() {
+            reader.error(key, "must not be empty");
+        }
+        !value.is_empty()
+    })?;
+    match key {
+        "scriptMarker" => Some(Some(ScriptLocator::Marker(value))),
+        _ => match Regex::new(&value) {
+            Ok(pattern) => Some(Some(ScriptLocator::Pattern(pattern))),
+            Err(error) => {
+                reader.error(key, format!("is not a valid regex ({})", error));
+                None
+            }
+        },
+    }
+}
+
+/// Reads the named `fields` of the HTML tags. Each one is either a tag,
+/// or a table with an optional `tag` and `attribute`.
+fn read_html_fields(reader: &TableReader) -> Option<HashMap<String, HtmlField>> {
+    let table = match reader.has("fields") {
+        true => reader.table("fields")?,
+        false => return Some(HashMap::new()),
+    };
+
+    let mut fields = HashMap::new();
+    let mut valid = true;
+    for name in table.keys() {
+        match read_html_field(&table, name) {
+            Some(field) => {
+                fields.insert(name.to_owned(), field);
+            }
+            None => valid = false,
+        }
+    }
+
+    valid.then_some(fields)
+}
+
+/// Reads where a value is inside a HTML element,
+/// either as a tag or as a table with an optional `tag` and `attribute`.
+pub fn read_html_field(reader: &TableReader, key: &str) -> Option<HtmlField> {
+    match reader.get(key) {
+        Some(TomlValue::String(tag)) => Some(HtmlField {
+            tag: Some(tag.to_owned()),
+            attribute: None,
+        }),
+        Some(TomlValue::Table(_)) => reader.table(key).map(|field| {
+            field.deny_unknown_keys(&["tag", "attribute"]);
+            HtmlField {
+                tag: field.non_empty_string("tag"),
+                attribute: field.non_empty_string("attribute"),
+            }
+        }),
+        _ => {
+            reader.error(key, "must be a tag or a table with tag and attribute");
+            None
+        }
+    }
+}
+
+pub fn tags_schema() -> JsonValue {
+    let mut properties = Map::new();
+    for key in TARGET_TAGS_KEYS {
+        properties.insert(key.to_owned(), json!({ "type": "string" }));
+    }
+    properties.insert(
+        String::from("dateFormat"),
+        json!({
+            "type": "string",
+            "description": "A strftime format, or \"relative\" for dates like 3日前, 2 hours ago or 昨日. Without it, ISO 8601 and Japanese dates (2023年11月2日, 11/2(木)) are read.",
+        }),
+    );
+    properties.insert(
+        String::from("skip"),
+        json!({
+            "type": "array",
+            "description": "html: chapter elements matching any of these rules are skipped.",
+            "items": {
+                "type": "object",
+                "additionalProperties": false,
+                "minProperties": 1,
+                "not": { "required": ["equals", "matches"] },
+                "properties": {
+                    "tag": {
+                        "type": "string",
+                        "description": "CSS selector, matched against the element itself and its descendants.",
+                    },
+                    "attribute": { "type": "string" },
+                    "equals": {
+                        "type": "string",
+                        "description": "Skip if the trimmed text (or attribute value) is exactly this.",
+                    },
+                    "matches": {
+                        "type": "string",
+                        "description": "Skip if the text (or attribute value) matches this regex.",
+                    },
+                },
+            },
+        }),
+    );
+    properties.insert(
+        String::from("scriptMarker"),
+        json!({
+            "type": "string",
+            "description": "json_in_html: the JSON follows this text in the script, e.g. \"window.__INITIAL_STATE__\".",
+        }),
+    );
+    properties.insert(
+        String::from("scriptPattern"),
+        json!({
+            "type": "string",
+            "description": "json_in_html: regex locating the JSON in the script. It starts at the first capture group, or after the match.",
+        }),
+    );
+    properties.insert(
+        String::from("fields"),
+        json!({
+            "type": "object",
+            "description": "Extra named values for titleTemplate.",
+            "additionalProperties": {
+                "oneOf": [
+                    { "type": "string" },
+                    {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "tag": { "type": "string" },
+                            "attribute": { "type": "string" },
+                        },
+                    },
+                ],
+            },
+        }),
+    );
+
+    json!({
+        "type": "object",
+        "description": "CSS selectors and attributes of the chapter values in an HTML source.",
+        "additionalProperties": false,
+        "required": ["chaptersTag"],
+        "not": { "required": ["scriptMarker", "scriptPattern"] },
+        "properties": properties,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::DateTime;
+    use regex::Regex;
+    use scraper::Html;
+
+    use crate::structs::{HtmlSkipRule, ParseMode, ParseRules, SkipCondition, Target, TargetTags};
+
+    use super::{
+        is_skipped, make_selector, parse_html, parse_string_to_datetime, read_html_skip_rule,
+        HtmlParser,
+    };
+
+    #[test]
+    fn test_parse_html() {
+        let target = Target {
+            mode: ParseMode(&HtmlParser),
+            base_url: Some("https://comic-html.com".into()),
+            delay: Some(7),
+            rules: ParseRules::new(TargetTags {
+                chapters_tag: "div#chapterlist li".into(),
+                number_tag: None,
+                number_attribute: Some("data-num".into()),
+                title_tag: Some("div div a span.chapternum".into()),
+                title_attribute: None,
+                date_tag: Some("div div a span.chapterdate".into()),
+                date_attribute: None,
+                date_format: Some("%B %-d, %Y".into()),
+                url_tag: Some("div div a".into()),
+                url_attribute: Some("href".into()),
+                thumbnail_tag: Some("img.thumbnail".into()),
+                thumbnail_attribute: Some("src".into()),
+                script: None,
+                skip: vec![],
+                fields: HashMap::new(),
+            }),
+            ..Target::for_test("Test Manga", "https://comic-html.com/test.html")
+        };
+
+        let source = r###"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "https://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">
+        <html xmlns="https://www.w3.org/1999/xhtml" lang="ja">
+        <head>
+            <title>Test Manga</title>
+        </head>
+        <body>
+            <div id="content">
+                <div class="wrapper">
+                    <article>
+                        <div class="eplister" id="chapterlist">
+                            <ul class="clstyle">
+                                <li data-num="51">
+                                    <div class="chbox">
+                                        <div class="eph-num">
+                                            <img class="thumbnail" src="/images/51.jpg" />
+                                            <a href="https://comic-html.com/chapter/51">
+                                                <span class="chapternum">Chapter 51</span>
+                                                <span class="chapterdate">June 3, 2022</span>
+                                            </a>
+                                        </div>
+                                    </div>
+                                </li>
+                                <li data-num="50">
+                                    <div class="chbox">
+                                        <div class="eph-num">
+                                            <a>
+                                                <span class="chapternum">Chapter Breaker</span>
+                                                <span class="chapterdate">May 29, 2022</span>
+                                            </a>
+                                        </div>
+                                    </div>
+                                </li>
+                                <li data-num="50">
+                                    <div class="chbox">
+                                        <div class="eph-num">
+                                            <a href="https://comic-html.com/chapter/50">
+                                                <span class="chapternum">Chapter 50</span>
+                                                <span class="chapterdate">May 15, 2022</span>
+                                            </a>
+                                        </div>
+                                    </div>
+                                </li>
+                            </ul>
+                        </div>
+                    </article>
+                </div>
+            </div>
+        </body>
+        </html>"###;
+        let chapters = parse_html(&target, source).unwrap();
+
+        // Should have 2 chapters
+        assert!(chapters.len() == 2);
+        // Check manga title
+        assert_eq!(chapters[0].manga, "Test Manga");
+        assert_eq!(chapters[0].manga, chapters[1].manga);
+        // Check numbers (chapter IDs)
+        assert_eq!(chapters[0].number, "50");
+        assert_eq!(chapters[1].number, "51");
+        // Check titles
+        assert_eq!(chapters[0].title, "Chapter 50");
+        assert_eq!(chapters[1].title, "Chapter 51");
+        // Check links
+        assert_eq!(chapters[0].url, "https://comic-html.com/chapter/50");
+        assert_eq!(chapters[1].url, "https://comic-html.com/chapter/51");
+        // Check thumbnails
+        assert_eq!(chapters[0].thumbnail, None);
+        assert_eq!(
+            chapters[1].thumbnail.as_deref(),
+            Some("https://comic-html.com/images/51.jpg"),
+        );
+        // Check dates
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc3339("2022-05-15T00:00:00.000+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].date,
+            DateTime::parse_from_rfc3339("2022-06-03T00:00:00.000+00:00").unwrap(),
+        );
+        // Check announce time
+        assert_eq!(
+            chapters[0].announced_at,
+            DateTime::parse_from_rfc3339("2022-05-22T00:00:00.000+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].announced_at,
+            DateTime::parse_from_rfc3339("2022-06-10T00:00:00.000+00:00").unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_parse_string_to_datetime() {
+        let tokyo = Some(chrono_tz::Asia::Tokyo);
+        let parse = |date: &str, format: Option<&str>| {
+            parse_string_to_datetime(date, &format.map(String::from), &tokyo).unwrap()
+        };
+
+        // Dates without an offset are in the target's time zone
+        assert_eq!(
+            parse("2023/11/02", Some("%Y/%m/%d")),
+            DateTime::parse_from_rfc3339("2023-11-01T15:00:00+00:00").unwrap(),
+        );
+        assert_eq!(
+            parse("2023-11-02 10:30:00", Some("%Y-%m-%d %H:%M:%S")),
+            DateTime::parse_from_rfc3339("2023-11-02T01:30:00+00:00").unwrap(),
+        );
+        // Weekdays after the date are left out
+        assert_eq!(
+            parse("2023/11/02(木)", Some("%Y/%m/%d")),
+            DateTime::parse_from_rfc3339("2023-11-01T15:00:00+00:00").unwrap(),
+        );
+        // Explicit offsets take precedence
+        assert_eq!(
+            parse("2023-11-02 10:30:00 +0000", Some("%Y-%m-%d %H:%M:%S %z")),
+            DateTime::parse_from_rfc3339("2023-11-02T10:30:00+00:00").unwrap(),
+        );
+        assert_eq!(
+            parse("2023-11-02T10:30:00-05:00", None),
+            DateTime::parse_from_rfc3339("2023-11-02T15:30:00+00:00").unwrap(),
+        );
+        // UTC without a time zone
+        assert_eq!(
+            parse_string_to_datetime("2023/11/02", &Some("%Y/%m/%d".into()), &None).unwrap(),
+            DateTime::parse_from_rfc3339("2023-11-02T00:00:00+00:00").unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_is_skipped() {
+        let rules = vec![
+            HtmlSkipRule {
+                tag: Some(make_selector(".is-locked").unwrap()),
+                attribute: None,
+                condition: None,
+            },
+            HtmlSkipRule {
+                tag: None,
+                attribute: Some("data-status".into()),
+                condition: Some(SkipCondition::Equals("upcoming".into())),
+            },
+            HtmlSkipRule {
+                tag: Some(make_selector("span.badge").unwrap()),
+                attribute: None,
+                condition: Some(SkipCondition::Matches(
+                    Regex::new("(?i)有料|coming soon").unwrap(),
+                )),
+            },
+        ];
+
+        let html = Html::parse_fragment(
+            r#"<ul>
+              <li class="episode">Free</li>
+              <li class="episode is-locked">Locked</li>
+              <li class="episode"><i class="is-locked"></i>Locked icon</li>
+              <li class="episode" data-status="upcoming">Upcoming</li>
+              <li class="episode" data-status="public">Public</li>
+              <li class="episode"><span class="badge">Coming Soon</span></li>
+              <li class="episode"><span class="badge">New</span></li>
+            </ul>"#,
+        );
+        let selector = make_selector("li.episode").unwrap();
+        let skipped: Vec<bool> = html
+            .select(&selector)
+            .map(|element| is_skipped(&element, &rules))
+            .collect();
+
+        assert_eq!(skipped, [false, true, true, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_read_html_skip_rule() {
+        let read = |rule: &str| read_html_skip_rule(&rule.parse::<toml::Value>().unwrap());
+
+        assert_eq!(
+            read(r#"tag = "span.badge""#).unwrap().tag,
+            Some(make_selector("span.badge").unwrap()),
+        );
+        // Selectors are checked when the settings are read
+        assert_eq!(
+            read(r#"tag = "li > > span""#).unwrap_err(),
+            "tag is not a valid selector (li > > span)",
+        );
+        assert_eq!(
+            read("tag = 'li'\nequals = 'a'\nmatches = 'b'").unwrap_err(),
+            "cannot have both equals and matches",
+        );
+    }
+}
@@ -1,269 +1,534 @@
-use anyhow::{anyhow, Result};
-use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-use json_dotpath::DotPaths;
-use serde_json::Value;
-
-use crate::structs::{
-    Chapter,
-    JsonDateTimeFormat::{Rfc2822, Rfc3339, StringFormat, UnixMilli, UnixNano, UnixSec},
-    Target,
-};
-
-use super::utils::make_link;
-
-fn parse_date_rfc2822(date_string: &str) -> Result<DateTime<Utc>> {
-    let dt = DateTime::parse_from_rfc2822(date_string)?;
-    Ok(dt.into())
-}
-
-fn parse_date_rfc3339(date_string: &str) -> Result<DateTime<Utc>> {
-    let dt = DateTime::parse_from_rfc3339(date_string)?;
-    Ok(dt.into())
-}
-
-fn parse_date_unix_seconds(timestamp: i64) -> Result<DateTime<Utc>> {
-    let dt = Utc.timestamp_opt(timestamp, 0).latest().unwrap();
-    Ok(dt.into())
-}
-
-fn parse_date_unix_millis(timestamp: i64) -> Result<DateTime<Utc>> {
-    let dt = Utc.timestamp_millis_opt(timestamp).latest().unwrap();
-    Ok(dt.into())
-}
-
-fn parse_date_unix_nanos(timestamp: i64) -> Result<DateTime<Utc>> {
-    let dt = Utc.timestamp_nanos(timestamp);
-    Ok(dt.into())
-}
-
-fn parse_date_custom_format(date_string: &str, date_format: &str) -> Result<DateTime<Utc>> {
-    let has_time = date_format.contains("%H");
-
-    match has_time {
-        true => parse_date_custom_format_with_time(date_string, date_format),
-        false => parse_date_custom_format_without_time(date_string, date_format),
-    }
-}
-
-fn parse_date_custom_format_with_time(
-    date_string: &str,
-    date_format: &str,
-) -> Result<DateTime<Utc>> {
-    match NaiveDateTime::parse_from_str(date_string, date_format) {
-        Ok(naive_date) => {
-            let convert = Utc.from_local_datetime(&naive_date).unwrap();
-            Ok(convert)
-        }
-        Err(e) => Err(e.into()),
-    }
-}
-
-fn parse_date_custom_format_without_time(
-    date_string: &str,
-    date_format: &str,
-) -> Result<DateTime<Utc>> {
-    match NaiveDate::parse_from_str(date_string, date_format) {
-        Ok(naive_date) => {
-            let naive_time =
-                NaiveTime::from_hms_opt(0, 0, 0).ok_or(anyhow!("Could not create NaiveTime"))?;
-            let naive_datetime = naive_date.and_time(naive_time);
-            let convert = Utc.from_local_datetime(&naive_datetime).unwrap();
-            Ok(convert)
-        }
-        Err(e) => Err(e.into()),
-    }
-}
-
-fn convert_value_into_string(value: Value) -> Result<String> {
-    if value.is_string() {
-        let the_str = value
-            .as_str()
-            .ok_or(anyhow!("Could not convert to string"))?;
-        return Ok(the_str.into());
-    } else if value.is_number() {
-        let the_number = value.as_i64().ok_or(anyhow!("Could not convert to i64"))?;
-        return Ok(the_number.to_string());
-    }
-
-    Err(anyhow!("Value isn't a valid type"))
-}
-
-pub fn parse_json(target: &Target, source: &str) -> Result<Vec<Chapter>> {
-    let mut chapters: Vec<Chapter> = vec![];
-    let json: Value = serde_json::from_str(source)?;
-    let keys = target.keys.as_ref().unwrap();
-
-    let chapters_json: Value = json.dot_get(&keys.chapters)?.unwrap();
-    let chapters_json = chapters_json.as_array().unwrap();
-
-    'outer: for chapter_json in chapters_json {
-        for skip_condition in &keys.skip {
-            let value: Option<Value> = chapter_json.dot_get(skip_condition.0)?;
-            if value.is_none() {
-                continue;
-            }
-
-            let value = value.unwrap();
-            if value.eq(skip_condition.1) {
-                continue 'outer;
-            }
-        }
-
-        let mixer = |keys: &Vec<String>| -> Result<String> {
-            let mut vec = vec![];
-            for key in keys {
-                let value: Value = chapter_json
-                    .dot_get(key)?
-                    .ok_or(anyhow!("Could not get value"))?;
-                let string = convert_value_into_string(value)?;
-                vec.push(string);
-            }
-            Ok(vec.join(" "))
-        };
-
-        let number = mixer(&keys.number)?;
-        let title = mixer(&keys.title)?;
-
-        let date: Value = chapter_json.dot_get(&keys.date)?.unwrap();
-        let date = match &keys.date_format {
-            Some(format) => match format {
-                UnixSec => parse_date_unix_seconds(date.as_i64().unwrap()),
-                UnixMilli => parse_date_unix_millis(date.as_i64().unwrap()),
-                UnixNano => parse_date_unix_nanos(date.as_i64().unwrap()),
-                Rfc2822 => parse_date_rfc2822(date.as_str().unwrap()),
-                Rfc3339 => parse_date_rfc3339(date.as_str().unwrap()),
-                StringFormat(format) => parse_date_custom_format(date.as_str().unwrap(), format),
-            },
-            None => parse_date_rfc3339(date.as_str().unwrap()),
-        }?;
-
-        let url: Value = chapter_json.dot_get(&keys.url)?.unwrap();
-        let url = convert_value_into_string(url)?;
-
-        chapters.push(Chapter {
-            manga: target.name.to_owned(),
-            number: number,
-            title: title,
-            date: date,
-            url: match &target.base_url {
-                Some(base_url) => make_link(&base_url, &url),
-                None => url,
-            },
-            logged_at: None,
-            announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
-        })
-    }
-
-    if !target.ascending_source {
-        chapters.reverse();
-    }
-
-    Ok(chapters)
-}
-
-#[cfg(test)]
-mod test {
-    use std::collections::HashMap;
-
-    use chrono::DateTime;
-    use serde_json::Value;
-
-    use crate::structs::{ParseMode, Target, TargetKeys};
-
-    use super::parse_json;
-
-    #[test]
-    fn test_parse_json() {
-        let target = Target {
-            name: "Test Manga".into(),
-            source: "https://comic-json.com/test.json".into(),
-            ascending_source: false,
-            mode: ParseMode::Json,
-            base_url: Some("https://comic-json.com".into()),
-            request_headers: None,
-            delay: None,
-            keys: Some(TargetKeys {
-                chapters: "comic.episodes".into(),
-                number: vec!["volume".into()],
-                title: vec!["volume".into(), "title".into()],
-                date: "publish_start".into(),
-                date_format: None,
-                url: "page_url".into(),
-                skip: HashMap::from([(String::from("readable"), Value::Bool(false))]),
-            }),
-            tags: None,
-        };
-
-        let source = r###"{
-            "comic": {
-                "episodes": [
-                    {
-                        "id": 16255,
-                        "volume": "Chapter 106",
-                        "sort_volume": 113,
-                        "page_count": 0,
-                        "title": "Dat Boi",
-                        "publish_start": "2022-10-11T10:00:00.000+09:00",
-                        "publish_end": "2022-11-22T10:00:00.000+09:00",
-                        "member_publish_start": "2022-10-11T10:00:00.000+09:00",
-                        "member_publish_end": "2022-11-22T10:00:00.000+09:00",
-                        "status": "public",
-                        "page_url": "/comics/json/113",
-                        "readable": true
-                    },
-                    {
-                        "readable": false,
-                        "title": "Sum Kinda Separator Thing"
-                    },
-                    {
-                        "id": 16180,
-                        "volume": "Chapter 105",
-                        "sort_volume": 112,
-                        "page_count": 0,
-                        "title": "Here comes",
-                        "publish_start": "2022-09-27T10:00:00.000+09:00",
-                        "publish_end": "2022-11-08T10:00:00.000+09:00",
-                        "member_publish_start": "2022-09-27T10:00:00.000+09:00",
-                        "member_publish_end": "2022-11-08T10:00:00.000+09:00",
-                        "status": "public",
-                        "page_url": "/comics/json/112"
-                    }
-                ]
-            }
-        }"###;
-        let chapters = parse_json(&target, source).unwrap();
-
-        // Should have 2 chapters
-        assert!(chapters.len() == 2);
-        // Check manga title
-        assert_eq!(chapters[0].manga, "Test Manga");
-        assert_eq!(chapters[0].manga, chapters[1].manga);
-        // Check numbers (chapter IDs)
-        assert_eq!(chapters[0].number, "Chapter 105");
-        assert_eq!(chapters[1].number, "Chapter 106");
-        // Check titles
-        assert_eq!(chapters[0].title, "Chapter 105 Here comes");
-        assert_eq!(chapters[1].title, "Chapter 106 Dat Boi");
-        // Check links
-        assert_eq!(chapters[0].url, "https://comic-json.com/comics/json/112");
-        assert_eq!(chapters[1].url, "https://comic-json.com/comics/json/113");
-        // Check dates
-        assert_eq!(
-            chapters[0].date,
-            DateTime::parse_from_rfc3339("2022-09-27T10:00:00.000+09:00").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].date,
-            DateTime::parse_from_rfc3339("2022-10-11T10:00:00.000+09:00").unwrap(),
-        );
-        // Check announce time
-        assert_eq!(
-            chapters[0].announced_at,
-            DateTime::parse_from_rfc3339("2022-09-27T10:00:00.000+09:00").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].announced_at,
-            DateTime::parse_from_rfc3339("2022-10-11T10:00:00.000+09:00").unwrap(),
-        );
-    }
-}
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+
+use crate::{
+    config::{conditions::read_conditions, reader::TableReader},
+    structs::{
+        Chapter, JsonDateTimeFormat,
+        JsonDateTimeFormat::{Rfc2822, Rfc3339, StringFormat, UnixMilli, UnixNano, UnixSec},
+        ParseRules, Target, TargetKeys,
+    },
+};
+
+use super::{
+    condition::is_skipped,
+    dates::{parse_relative_date, RELATIVE_FORMAT},
+    json_path::JsonPath,
+    parser::Parser,
+    readers::{parse_json_path, parse_json_paths, read_json_date_format, read_json_path_table},
+    transform::apply_transforms,
+    utils::{make_image_link, make_link, naive_to_utc},
+};
+
+/// Keys allowed in the `keys` table of a target.
+pub const TARGET_KEYS_KEYS: [&str; 10] = [
+    "chapters",
+    "number",
+    "title",
+    "date",
+    "dateFormat",
+    "url",
+    "skip",
+    "include",
+    "thumbnail",
+    "fields",
+];
+
+fn parse_date_rfc2822(date_string: &str) -> Result<DateTime<Utc>> {
+    let dt = DateTime::parse_from_rfc2822(date_string)?;
+    Ok(dt.into())
+}
+
+/// Dates without an offset (e.g. `2023-11-02T10:00:00`) are read in the target's time zone.
+fn parse_date_rfc3339(date_string: &str, timezone: &Option<Tz>) -> Result<DateTime<Utc>> {
+    match DateTime::parse_from_rfc3339(date_string) {
+        Ok(dt) => Ok(dt.into()),
+        Err(error) => match NaiveDateTime::from_str(date_string) {
+            Ok(naive_date) => naive_to_utc(&naive_date, timezone),
+            Err(_) => Err(error.into()),
+        },
+    }
+}
+
+fn parse_date_unix_seconds(timestamp: i64) -> Result<DateTime<Utc>> {
+    let dt = Utc.timestamp_opt(timestamp, 0).latest().unwrap();
+    Ok(dt)
+}
+
+fn parse_date_unix_millis(timestamp: i64) -> Result<DateTime<Utc>> {
+    let dt = Utc.timestamp_millis_opt(timestamp).latest().unwrap();
+    Ok(dt)
+}
+
+fn parse_date_unix_nanos(timestamp: i64) -> Result<DateTime<Utc>> {
+    let dt = Utc.timestamp_nanos(timestamp);
+    Ok(dt)
+}
+
+fn parse_date_custom_format(
+    date_string: &str,
+    date_format: &str,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    if date_format == RELATIVE_FORMAT {
+        return parse_relative_date(date_string, Utc::now(), timezone);
+    }
+
+    // An offset in the date (`%z`) takes precedence over the target's time zone
+    if let Ok(dt) = DateTime::parse_from_str(date_string, date_format) {
+        return Ok(dt.into());
+    }
+
+    let has_time = date_format.contains("%H");
+
+    match has_time {
+        true => parse_date_custom_format_with_time(date_string, date_format, timezone),
+        false => parse_date_custom_format_without_time(date_string, date_format, timezone),
+    }
+}
+
+fn parse_date_custom_format_with_time(
+    date_string: &str,
+    date_format: &str,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    match NaiveDateTime::parse_from_str(date_string, date_format) {
+        Ok(naive_date) => naive_to_utc(&naive_date, timezone),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn parse_date_custom_format_without_time(
+    date_string: &str,
+    date_format: &str,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    match NaiveDate::parse_from_str(date_string, date_format) {
+        Ok(naive_date) => {
+            let naive_time =
+                NaiveTime::from_hms_opt(0, 0, 0).ok_or(anyhow!("Could not create NaiveTime"))?;
+            let naive_datetime = naive_date.and_time(naive_time);
+            naive_to_utc(&naive_datetime, timezone)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Parses a date value using the given format (RFC 3339 by default).
+pub(super) fn parse_date(
+    date: &Value,
+    format: &Option<JsonDateTimeFormat>,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    match date {
+        Value::String(date) => parse_date_string(date, format, timezone),
+        Value::Number(_) => parse_date_string(&date.to_string(), format, timezone),
+        _ => Err(anyhow!("Date {} isn't a string or a number", date)),
+    }
+}
+
+/// Parses a date written as text. Timestamps are numbers written as text as well.
+/// Dates without an offset are read in the target's time zone.
+pub(super) fn parse_date_string(
+    date: &str,
+    format: &Option<JsonDateTimeFormat>,
+    timezone: &Option<Tz>,
+) -> Result<DateTime<Utc>> {
+    let timestamp = || {
+        date.trim()
+            .parse::<i64>()
+            .map_err(|_| anyhow!("Date {} isn't a timestamp", date))
+    };
+
+    match format {
+        Some(UnixSec) => parse_date_unix_seconds(timestamp()?),
+        Some(UnixMilli) => parse_date_unix_millis(timestamp()?),
+        Some(UnixNano) => parse_date_unix_nanos(timestamp()?),
+        Some(Rfc2822) => parse_date_rfc2822(date),
+        Some(Rfc3339) | None => parse_date_rfc3339(date, timezone),
+        Some(StringFormat(format)) => parse_date_custom_format(date, format, timezone),
+    }
+}
+
+pub(super) fn convert_value_into_string(value: &Value) -> Result<String> {
+    if value.is_string() {
+        let the_str = value
+            .as_str()
+            .ok_or(anyhow!("Could not convert to string"))?;
+        return Ok(the_str.into());
+    } else if value.is_number() {
+        let the_number = value.as_i64().ok_or(anyhow!("Could not convert to i64"))?;
+        return Ok(the_number.to_string());
+    }
+
+    Err(anyhow!("Value isn't a valid type"))
+}
+
+/// Gets the value at a path inside a chapter.
+pub(super) fn get_path<'a>(chapter_json: &'a Value, path: &JsonPath) -> Result<&'a Value> {
+    path.get(chapter_json)
+        .ok_or(anyhow!("Could not get value at {}", path))
+}
+
+/// Gets every chapter matched by the `chapters` path.
+/// Matched arrays are flattened, so paths with wildcards can gather chapters from several lists.
+fn get_chapters<'a>(json: &'a Value, path: &JsonPath) -> Result<Vec<&'a Value>> {
+    let matches = path.query(json);
+    if matches.is_empty() {
+        return Err(anyhow!("Could not find chapters at {}", path));
+    }
+
+    let mut chapters = vec![];
+    for value in matches {
+        match value {
+            Value::Array(array) => chapters.extend(array),
+            value => chapters.push(value),
+        }
+    }
+
+    Ok(chapters)
+}
+
+/// Gets the `keys` of a `json` target.
+pub fn keys(rules: &ParseRules) -> Result<&TargetKeys> {
+    rules.get().ok_or(anyhow!("Target has no keys"))
+}
+
+pub fn parse_json(target: &Target, source: &str) -> Result<Vec<Chapter>> {
+    let json: Value = serde_json::from_str(source)?;
+    parse_json_value(target, keys(&target.rules)?, &json)
+}
+
+/// Reads the chapters out of JSON that has already been parsed, with the given `keys`.
+pub fn parse_json_value(target: &Target, keys: &TargetKeys, json: &Value) -> Result<Vec<Chapter>> {
+    let mut chapters: Vec<Chapter> = vec![];
+
+    let chapters_json = get_chapters(json, &keys.chapters)?;
+
+    for chapter_json in chapters_json {
+        if is_skipped(keys, chapter_json, &target.timezone)? {
+            continue;
+        }
+
+        let mixer = |keys: &Vec<JsonPath>| -> Result<String> {
+            let mut vec = vec![];
+            for key in keys {
+                let value = get_path(chapter_json, key)?;
+                let string = convert_value_into_string(value)?;
+                vec.push(string);
+            }
+            Ok(vec.join(" "))
+        };
+
+        let number = mixer(&keys.number)?;
+        let title = match &target.title_template {
+            Some(template) => {
+                let title = match keys.title.is_empty() {
+                    true => None,
+                    false => Some(mixer(&keys.title)?),
+                };
+                template.render(|name| match name {
+                    "number" => Some(number.to_owned()),
+                    "title" => title.to_owned(),
+                    name => keys
+                        .fields
+                        .get(name)
+                        .and_then(|path| get_path(chapter_json, path).ok())
+                        .and_then(|value| convert_value_into_string(value).ok()),
+                })
+            }
+            None => mixer(&keys.title)?,
+        };
+
+        let date = get_path(chapter_json, &keys.date)?;
+        let date = parse_date(date, &keys.date_format, &target.timezone)?;
+
+        let url = get_path(chapter_json, &keys.url)?;
+        let url = convert_value_into_string(url)?;
+
+        let thumbnail = keys
+            .thumbnail
+            .as_ref()
+            .and_then(|path| get_path(chapter_json, path).ok())
+            .and_then(|value| convert_value_into_string(value).ok())
+            .and_then(|link| make_image_link(&target.base_url, &link));
+
+        chapters.push(Chapter {
+            manga: target.name.to_owned(),
+            number,
+            title,
+            date,
+            url: match &target.base_url {
+                Some(base_url) => make_link(base_url, &url),
+                None => url,
+            },
+            thumbnail,
+            logged_at: None,
+            announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
+        })
+    }
+
+    apply_transforms(target, &mut chapters);
+
+    if !target.ascending_source {
+        chapters.reverse();
+    }
+
+    Ok(chapters)
+}
+
+/// Parser of `mode = "json"`, reading the chapters with the JSON paths of its `keys` table.
+pub struct JsonParser;
+
+impl Parser for JsonParser {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn tables(&self) -> &'static [&'static str] {
+        &["keys"]
+    }
+
+    fn table_schema(&self, _table: &str) -> Value {
+        keys_schema()
+    }
+
+    fn read_rules(&self, target: &TableReader, has_template: bool) -> Option<ParseRules> {
+        let keys = read_keys(&target.table("keys")?, has_template)?;
+        Some(ParseRules::new(keys))
+    }
+
+    fn page_strategies(&self) -> &'static [&'static str] {
+        &["cursor"]
+    }
+
+    fn has_field(&self, rules: &ParseRules, name: &str) -> bool {
+        keys(rules).is_ok_and(|keys| keys.fields.contains_key(name))
+    }
+
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>> {
+        parse_json(target, body)
+    }
+}
+
+/// Reads the "parse keys" for a target that has a JSON source.
+/// `title` is optional when the target has a title template.
+pub fn read_keys(reader: &TableReader, has_template: bool) -> Option<TargetKeys> {
+    reader.deny_unknown_keys(&TARGET_KEYS_KEYS);
+
+    let path = |key: &str| {
+        reader
+            .required_string(key)
+            .and_then(|path| parse_json_path(reader, key, &path))
+    };
+    let paths = |key: &str| {
+        reader
+            .required_strings(key)
+            .and_then(|paths| parse_json_paths(reader, key, &paths))
+    };
+
+    let chapters = path("chapters");
+    let number = paths("number");
+    let title = match has_template && !reader.has("title") {
+        true => Some(vec![]),
+        false => paths("title"),
+    };
+    let date = path("date");
+    let date_format = read_json_date_format(reader);
+    let url = path("url");
+    let skip = read_conditions(reader, "skip");
+    let include = read_conditions(reader, "include");
+    let thumbnail = match reader.non_empty_string("thumbnail") {
+        Some(thumbnail) => parse_json_path(reader, "thumbnail", &thumbnail).map(Some),
+        None => Some(None),
+    };
+    let fields = read_json_path_table(reader, "fields");
+
+    Some(TargetKeys {
+        chapters: chapters?,
+        number: number?,
+        title: title?,
+        date: date?,
+        date_format,
+        url: url?,
+        skip: skip?,
+        include: include?,
+        thumbnail: thumbnail?,
+        fields: fields?,
+    })
+}
+
+pub fn keys_schema() -> Value {
+    let strings = json!({
+        "oneOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } },
+        ],
+    });
+
+    json!({
+        "type": "object",
+        "description": "JSONPath-style paths (dot paths, [*] wildcards, [?(@.key == value)] filters) of the chapter values in a JSON source.",
+        "additionalProperties": false,
+        "required": ["chapters", "number", "date", "url"],
+        "properties": {
+            "chapters": { "type": "string" },
+            "number": strings.clone(),
+            "title": strings,
+            "date": { "type": "string" },
+            "dateFormat": {
+                "type": "string",
+                "description": "\"unixsec\", \"unix\", \"unixmilli\", \"unixnano\", \"rfc2822\", \"rfc3339\", \"relative\" (e.g. 3日前, 2 hours ago) or a strftime format.",
+            },
+            "url": { "type": "string" },
+            "skip": {
+                "$ref": "#/definitions/conditions",
+                "description": "Chapters are skipped when any of these conditions holds.",
+            },
+            "include": {
+                "$ref": "#/definitions/conditions",
+                "description": "Chapters are skipped unless all of these conditions hold.",
+            },
+            "thumbnail": {
+                "type": "string",
+                "description": "Path of the chapter's thumbnail image URL.",
+            },
+            "fields": {
+                "type": "object",
+                "description": "Extra named values for titleTemplate.",
+                "additionalProperties": { "type": "string" },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::DateTime;
+    use serde_json::Value;
+
+    use crate::{
+        parsers::{json_path::JsonPath, template::TitleTemplate},
+        structs::{JsonCondition, ParseMode, ParseRules, Target, TargetKeys, ValueTest},
+    };
+
+    use super::{keys, parse_json, JsonParser};
+
+    #[test]
+    fn test_parse_json() {
+        let target = Target {
+            mode: ParseMode(&JsonParser),
+            base_url: Some("https://comic-json.com".into()),
+            rules: ParseRules::new(TargetKeys {
+                chapters: JsonPath::parse("comic.episodes").unwrap(),
+                number: vec![JsonPath::key("volume")],
+                title: vec![JsonPath::key("volume"), JsonPath::key("title")],
+                date: JsonPath::key("publish_start"),
+                date_format: None,
+                url: JsonPath::key("page_url"),
+                skip: vec![JsonCondition::Value {
+                    path: JsonPath::key("readable"),
+                    test: ValueTest::Equals(Value::Bool(false)),
+                }],
+                include: vec![],
+                thumbnail: None,
+                fields: HashMap::new(),
+            }),
+            ..Target::for_test("Test Manga", "https://comic-json.com/test.json")
+        };
+
+        let source = r###"{
+            "comic": {
+                "episodes": [
+                    {
+                        "id": 16255,
+                        "volume": "Chapter 106",
+                        "sort_volume": 113,
+                        "page_count": 0,
+                        "title": "Dat Boi",
+                        "publish_start": "2022-10-11T10:00:00.000+09:00",
+                        "publish_end": "2022-11-22T10:00:00.000+09:00",
+                        "member_publish_start": "2022-10-11T10:00:00.000+09:00",
+                        "member_publish_end": "2022-11-22T10:00:00.000+09:00",
+                        "status": "public",
+                        "page_url": "/comics/json/113",
+                        "readable": true
+                    },
+                    {
+                        "readable": false,
+                        "title": "Sum Kinda Separator Thing"
+                    },
+                    {
+                        "id": 16180,
+                        "volume": "Chapter 105",
+                        "sort_volume": 112,
+                        "page_count": 0,
+                        "title": "Here comes",
+                        "publish_start": "2022-09-27T10:00:00.000+09:00",
+                        "publish_end": "2022-11-08T10:00:00.000+09:00",
+                        "member_publish_start": "2022-09-27T10:00:00.000+09:00",
+                        "member_publish_end": "2022-11-08T10:00:00.000+09:00",
+                        "status": "public",
+                        "page_url": "/comics/json/112"
+                    }
+                ]
+            }
+        }"###;
+        let chapters = parse_json(&target, source).unwrap();
+
+        // Should have 2 chapters
+        assert!(chapters.len() == 2);
+        // Check manga title
+        assert_eq!(chapters[0].manga, "Test Manga");
+        assert_eq!(chapters[0].manga, chapters[1].manga);
+        // Check numbers (chapter IDs)
+        assert_eq!(chapters[0].number, "Chapter 105");
+        assert_eq!(chapters[1].number, "Chapter 106");
+        // Check titles
+        assert_eq!(chapters[0].title, "Chapter 105 Here comes");
+        assert_eq!(chapters[1].title, "Chapter 106 Dat Boi");
+        // Check links
+        assert_eq!(chapters[0].url, "https://comic-json.com/comics/json/112");
+        assert_eq!(chapters[1].url, "https://comic-json.com/comics/json/113");
+        // Check dates
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc3339("2022-09-27T10:00:00.000+09:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].date,
+            DateTime::parse_from_rfc3339("2022-10-11T10:00:00.000+09:00").unwrap(),
+        );
+        // Check announce time
+        assert_eq!(
+            chapters[0].announced_at,
+            DateTime::parse_from_rfc3339("2022-09-27T10:00:00.000+09:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].announced_at,
+            DateTime::parse_from_rfc3339("2022-10-11T10:00:00.000+09:00").unwrap(),
+        );
+
+        // Titles from a template, dropping the section of a missing field
+        let mut target = target;
+        target.title_template = Some(TitleTemplate::parse("{number}[ ({pages} pages)]").unwrap());
+        target.rules = ParseRules::new(TargetKeys {
+            title: vec![],
+            fields: HashMap::from([(String::from("pages"), JsonPath::key("page_count"))]),
+            ..keys(&target.rules).unwrap().clone()
+        });
+        let source = source.replace(
+            "\"page_count\": 0,\n                        \"title\": \"Here comes\"",
+            "\"title\": \"Here comes\"",
+        );
+        let chapters = parse_json(&target, &source).unwrap();
+        assert_eq!(chapters[0].title, "Chapter 105");
+        assert_eq!(chapters[1].title, "Chapter 106 (0 pages)");
+    }
+}
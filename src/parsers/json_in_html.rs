@@ -1,145 +1,236 @@
-use anyhow::{anyhow, Result};
-use scraper::Html;
-
-use crate::structs::{Chapter, Target};
-
-use super::{
-    html::{get_value, make_selector},
-    json::parse_json,
-};
-
-pub fn parse_json_in_html(target: &Target, source: &str) -> Result<Vec<Chapter>> {
-    let html = Html::parse_document(source);
-    let tags = target.tags.as_ref().unwrap();
-
-    let selector = make_selector(&tags.chapters_tag)?;
-    let script_tag = html
-        .select(&selector)
-        .next()
-        .ok_or(anyhow!("Could not find script tag."))?;
-
-    let json = get_value(&script_tag, &None, &None)?;
-    parse_json(target, &json)
-}
-
-#[cfg(test)]
-mod test {
-    use std::collections::HashMap;
-
-    use chrono::DateTime;
-
-    use crate::{
-        parsers::json_in_html::parse_json_in_html,
-        structs::JsonDateTimeFormat::StringFormat,
-        structs::{ParseMode, Target, TargetKeys, TargetTags},
-    };
-
-    #[test]
-    fn test_parse_json() {
-        let target = Target {
-            name: "Test Manga".into(),
-            source: "https://comic-json.com/test.html".into(),
-            ascending_source: false,
-            mode: ParseMode::JsonInHtml,
-            base_url: Some("https://comic-json.com/viewer/".into()),
-            request_headers: None,
-            delay: None,
-            keys: Some(TargetKeys {
-                chapters: "props.pageProps.chapters.0.chapters".into(),
-                number: vec!["chapterId".into()],
-                title: vec!["chapterMainName".into()],
-                date: "updatedDate".into(),
-                date_format: Some(StringFormat("%Y/%m/%d".into())),
-                url: "chapterId".into(),
-                skip: HashMap::new(),
-            }),
-            tags: Some(TargetTags {
-                chapters_tag: "script#__NEXT_DATA__".into(),
-                number_tag: None,
-                number_attribute: None,
-                title_tag: None,
-                title_attribute: None,
-                date_tag: None,
-                date_attribute: None,
-                date_format: None,
-                url_tag: None,
-                url_attribute: None,
-            }),
-        };
-
-        let source = r###"<!DOCTYPE html>
-        <html>
-          <body>
-            <script id="__NEXT_DATA__" type="application/json">
-              {
-                "props": {
-                  "pageProps": {
-                    "chapters": [
-                      {
-                        "chapters": [
-                          {
-                            "chapterId": 48550,
-                            "chapterMainName": "CH 2",
-                            "thumbnailUrl": "/e/1Bg5XY/bSC.webp?h=x3dru-Omj0uL-7U7IXKt0Q\u0026e=5000000000",
-                            "pointConsumption": {},
-                            "numberOfComments": 72,
-                            "numberOfLikes": 1007,
-                            "updatedDate": "2023/11/02",
-                            "firstPageImageUrl": "/f/1Bg5Zu/bSC/0.jpeg.enc?h=9wW-UtXI8JNoAhfKMJu8HA\u0026e=1699369200"
-                          },
-                          {
-                            "chapterId": 48286,
-                            "chapterMainName": "CH 1",
-                            "thumbnailUrl": "/e/1Be9Ui/bOu.webp?h=-mKnKFH-wSrp7Q8MO7tK7g\u0026e=5000000000",
-                            "pointConsumption": {},
-                            "numberOfComments": 17,
-                            "numberOfLikes": 955,
-                            "updatedDate": "2023/10/26",
-                            "firstPageImageUrl": "/f/1Be9US/bOu/0.jpeg.enc?h=W5KMlod466p0jcovTgP1RQ\u0026e=1699369200"
-                          }
-                        ],
-                        "bookIssueHeader": { "text": "ABC" }
-                      }
-                    ]
-                  }
-                }
-              }
-            </script>
-          </body>
-        </html>"###;
-        let chapters = parse_json_in_html(&target, source).unwrap();
-
-        // Should have 2 chapters
-        assert!(chapters.len() == 2);
-        // Check manga title
-        assert_eq!(chapters[0].manga, "Test Manga");
-        assert_eq!(chapters[0].manga, chapters[1].manga);
-        // Check numbers (chapter IDs)
-        assert_eq!(chapters[0].number, "48286");
-        assert_eq!(chapters[1].number, "48550");
-        // Check titles
-        assert_eq!(chapters[0].title, "CH 1");
-        assert_eq!(chapters[1].title, "CH 2");
-        // Check links
-        assert_eq!(chapters[0].url, "https://comic-json.com/viewer/48286");
-        assert_eq!(chapters[1].url, "https://comic-json.com/viewer/48550");
-        // Check dates
-        assert_eq!(
-            chapters[0].date,
-            DateTime::parse_from_rfc3339("2023-10-26T00:00:00.000+00:00").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].date,
-            DateTime::parse_from_rfc3339("2023-11-02T00:00:00.000+00:00").unwrap(),
-        );
-        // Check announce time (same as dates)
-        assert_eq!(
-            chapters[0].announced_at,
-            DateTime::parse_from_rfc3339("2023-10-26T00:00:00.000+00:00").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].announced_at,
-            DateTime::parse_from_rfc3339("2023-11-02T00:00:00.000+00:00").unwrap(),
-        );
-    }
-}
+use anyhow::{anyhow, Result};
+use scraper::Html;
+use serde_json::Value as JsonValue;
+
+use crate::{
+    config::reader::TableReader,
+    structs::{Chapter, ParseRules, Target, TargetKeys, TargetTags},
+};
+
+use super::{
+    html::{get_value, make_selector, read_tags, tags_schema},
+    json::{keys_schema, parse_json_value, read_keys},
+    parser::Parser,
+    script_data::{extract_script_data, parse_json_text},
+};
+
+/// Rules of a `json_in_html` target: the `tags` locate the JSON, the `keys` read it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonInHtmlRules {
+    pub tags: TargetTags,
+    pub keys: TargetKeys,
+}
+
+/// Gets the `tags` and `keys` of a `json_in_html` target.
+pub fn tags_and_keys(rules: &ParseRules) -> Result<&JsonInHtmlRules> {
+    rules.get().ok_or(anyhow!("Target has no tags and keys"))
+}
+
+pub fn parse_json_in_html(target: &Target, source: &str) -> Result<Vec<Chapter>> {
+    let html = Html::parse_document(source);
+    let JsonInHtmlRules { tags, keys } = tags_and_keys(&target.rules)?;
+
+    let selector = make_selector(&tags.chapters_tag)?;
+    let mut script_tags = html.select(&selector);
+
+    let json = match &tags.script {
+        // The first script that contains the marker or pattern holds the data
+        Some(locator) => script_tags
+            .find_map(|script_tag| {
+                let text = get_value(&script_tag, &None, &None).ok()?;
+                extract_script_data(&text, locator)
+            })
+            .ok_or(anyhow!("Could not find the data in any script tag."))??,
+        None => {
+            let script_tag = script_tags
+                .next()
+                .ok_or(anyhow!("Could not find script tag."))?;
+            parse_json_text(&get_value(&script_tag, &None, &None)?)?
+        }
+    };
+
+    parse_json_value(target, keys, &json)
+}
+
+/// Parser of `mode = "json_in_html"`: the `tags` locate the JSON in the page,
+/// then the `keys` read the chapters like in `json` mode.
+pub struct JsonInHtmlParser;
+
+impl Parser for JsonInHtmlParser {
+    fn name(&self) -> &'static str {
+        "json_in_html"
+    }
+
+    fn tables(&self) -> &'static [&'static str] {
+        &["keys", "tags"]
+    }
+
+    fn table_schema(&self, table: &str) -> JsonValue {
+        match table {
+            "keys" => keys_schema(),
+            _ => tags_schema(),
+        }
+    }
+
+    fn read_rules(&self, target: &TableReader, has_template: bool) -> Option<ParseRules> {
+        let keys = target
+            .table("keys")
+            .and_then(|keys| read_keys(&keys, has_template));
+        let tags = target
+            .table("tags")
+            .and_then(|tags| read_tags(&tags, self.name()));
+
+        Some(ParseRules::new(JsonInHtmlRules {
+            tags: tags?,
+            keys: keys?,
+        }))
+    }
+
+    fn page_strategies(&self) -> &'static [&'static str] {
+        &["nextTag"]
+    }
+
+    fn has_field(&self, rules: &ParseRules, name: &str) -> bool {
+        tags_and_keys(rules).is_ok_and(|rules| rules.keys.fields.contains_key(name))
+    }
+
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>> {
+        parse_json_in_html(target, body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::DateTime;
+
+    use crate::{
+        parsers::{
+            json_in_html::{parse_json_in_html, JsonInHtmlParser, JsonInHtmlRules},
+            json_path::JsonPath,
+        },
+        structs::JsonDateTimeFormat::StringFormat,
+        structs::{ParseMode, ParseRules, Target, TargetKeys, TargetTags},
+    };
+
+    #[test]
+    fn test_parse_json() {
+        let target = Target {
+            mode: ParseMode(&JsonInHtmlParser),
+            base_url: Some("https://comic-json.com/viewer/".into()),
+            rules: ParseRules::new(JsonInHtmlRules {
+                keys: TargetKeys {
+                    chapters: JsonPath::parse("props.pageProps.chapters[*].chapters").unwrap(),
+                    number: vec![JsonPath::key("chapterId")],
+                    title: vec![JsonPath::key("chapterMainName")],
+                    date: JsonPath::key("updatedDate"),
+                    date_format: Some(StringFormat("%Y/%m/%d".into())),
+                    url: JsonPath::key("chapterId"),
+                    skip: vec![],
+                    include: vec![],
+                    thumbnail: Some(JsonPath::key("thumbnailUrl")),
+                    fields: HashMap::new(),
+                },
+                tags: TargetTags {
+                    chapters_tag: "script#__NEXT_DATA__".into(),
+                    number_tag: None,
+                    number_attribute: None,
+                    title_tag: None,
+                    title_attribute: None,
+                    date_tag: None,
+                    date_attribute: None,
+                    date_format: None,
+                    url_tag: None,
+                    url_attribute: None,
+                    thumbnail_tag: None,
+                    thumbnail_attribute: None,
+                    script: None,
+                    skip: vec![],
+                    fields: HashMap::new(),
+                },
+            }),
+            ..Target::for_test("Test Manga", "https://comic-json.com/test.html")
+        };
+
+        let source = r###"<!DOCTYPE html>
+        <html>
+          <body>
+            <script id="__NEXT_DATA__" type="application/json">
+              {
+                "props": {
+                  "pageProps": {
+                    "chapters": [
+                      {
+                        "chapters": [
+                          {
+                            "chapterId": 48550,
+                            "chapterMainName": "CH 2",
+                            "thumbnailUrl": "/e/1Bg5XY/bSC.webp?h=x3dru-Omj0uL-7U7IXKt0Q\u0026e=5000000000",
+                            "pointConsumption": {},
+                            "numberOfComments": 72,
+                            "numberOfLikes": 1007,
+                            "updatedDate": "2023/11/02",
+                            "firstPageImageUrl": "/f/1Bg5Zu/bSC/0.jpeg.enc?h=9wW-UtXI8JNoAhfKMJu8HA\u0026e=1699369200"
+                          },
+                          {
+                            "chapterId": 48286,
+                            "chapterMainName": "CH 1",
+                            "thumbnailUrl": "/e/1Be9Ui/bOu.webp?h=-mKnKFH-wSrp7Q8MO7tK7g\u0026e=5000000000",
+                            "pointConsumption": {},
+                            "numberOfComments": 17,
+                            "numberOfLikes": 955,
+                            "updatedDate": "2023/10/26",
+                            "firstPageImageUrl": "/f/1Be9US/bOu/0.jpeg.enc?h=W5KMlod466p0jcovTgP1RQ\u0026e=1699369200"
+                          }
+                        ],
+                        "bookIssueHeader": { "text": "ABC" }
+                      }
+                    ]
+                  }
+                }
+              }
+            </script>
+          </body>
+        </html>"###;
+        let chapters = parse_json_in_html(&target, source).unwrap();
+
+        // Should have 2 chapters
+        assert!(chapters.len() == 2);
+        // Check manga title
+        assert_eq!(chapters[0].manga, "Test Manga");
+        assert_eq!(chapters[0].manga, chapters[1].manga);
+        // Check numbers (chapter IDs)
+        assert_eq!(chapters[0].number, "48286");
+        assert_eq!(chapters[1].number, "48550");
+        // Check titles
+        assert_eq!(chapters[0].title, "CH 1");
+        assert_eq!(chapters[1].title, "CH 2");
+        // Check links
+        assert_eq!(chapters[0].url, "https://comic-json.com/viewer/48286");
+        assert_eq!(chapters[1].url, "https://comic-json.com/viewer/48550");
+        // Check thumbnails (relative to the base URL's host)
+        assert_eq!(
+            chapters[0].thumbnail.as_deref(),
+            Some("https://comic-json.com/e/1Be9Ui/bOu.webp?h=-mKnKFH-wSrp7Q8MO7tK7g&e=5000000000"),
+        );
+        // Check dates
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc3339("2023-10-26T00:00:00.000+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].date,
+            DateTime::parse_from_rfc3339("2023-11-02T00:00:00.000+00:00").unwrap(),
+        );
+        // Check announce time (same as dates)
+        assert_eq!(
+            chapters[0].announced_at,
+            DateTime::parse_from_rfc3339("2023-10-26T00:00:00.000+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].announced_at,
+            DateTime::parse_from_rfc3339("2023-11-02T00:00:00.000+00:00").unwrap(),
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::{cmp::Ordering, fmt};
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+
+/// A JSONPath-style expression, e.g. `props.pageProps.chapters[*].chapters[?(@.readable == true)]`.
+///
+/// Supports `.key`, `['key']`, `[0]` (or `.0`), `*`/`[*]` wildcards and `[?(@.key <op> value)]` filters,
+/// where `<op>` is one of `==`, `!=`, `<`, `<=`, `>` or `>=`. A filter without an operator
+/// (`[?(@.key)]`) keeps the items whose value exists and is not `false` or `null`.
+/// The leading `$` is optional, so plain dot paths such as `data.episodes` are valid as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    /// The path as written in the config, for messages.
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// Object key, or array index if the key is numeric and the value is an array.
+    Key(String),
+    Index(usize),
+    Wildcard,
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    path: JsonPath,
+    condition: Option<(Operator, Value)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Operators allowed in filters. When several match at the same position the longest one wins.
+const OPERATORS: [(&str, Operator); 6] = [
+    ("==", Operator::Equal),
+    ("!=", Operator::NotEqual),
+    ("<=", Operator::LessOrEqual),
+    (">=", Operator::GreaterOrEqual),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+];
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self> {
+        let mut rest = path.trim();
+        rest = rest.strip_prefix('$').unwrap_or(rest);
+
+        let mut segments = vec![];
+        let mut first = true;
+        while !rest.is_empty() {
+            if let Some(bracketed) = rest.strip_prefix('[') {
+                let end = find_closing_bracket(bracketed)
+                    .ok_or(anyhow!("\"[\" without a closing \"]\""))?;
+                segments.push(parse_bracket(bracketed[..end].trim())?);
+                rest = &bracketed[end + 1..];
+            } else {
+                let name_start = match rest.strip_prefix('.') {
+                    Some(after_dot) => after_dot,
+                    None if first => rest,
+                    None => bail!("unexpected \"{}\"", rest),
+                };
+                let end = name_start.find(['.', '[']).unwrap_or(name_start.len());
+                let name = &name_start[..end];
+                match name {
+                    "" => bail!("empty key"),
+                    "*" => segments.push(Segment::Wildcard),
+                    name => segments.push(Segment::Key(name.to_owned())),
+                }
+                rest = &name_start[end..];
+            }
+            first = false;
+        }
+
+        Ok(Self {
+            source: path.to_owned(),
+            segments,
+        })
+    }
+
+    /// A path to a single key, without reading any syntax in it.
+    pub fn key(key: &str) -> Self {
+        Self {
+            source: key.to_owned(),
+            segments: vec![Segment::Key(key.to_owned())],
+        }
+    }
+
+    /// Gets every value matched by the path.
+    pub fn query<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            let mut next = vec![];
+            for value in current {
+                segment.apply(value, &mut next);
+            }
+            current = next;
+        }
+
+        current
+    }
+
+    /// Gets the first value matched by the path.
+    pub fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.query(value).into_iter().next()
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Segment {
+    fn apply<'a>(&self, value: &'a Value, results: &mut Vec<&'a Value>) {
+        match (self, value) {
+            (Segment::Key(key), Value::Object(object)) => results.extend(object.get(key)),
+            (Segment::Key(key), Value::Array(array)) => {
+                if let Ok(index) = key.parse::<usize>() {
+                    results.extend(array.get(index));
+                }
+            }
+            (Segment::Index(index), Value::Array(array)) => results.extend(array.get(*index)),
+            (Segment::Wildcard, Value::Array(array)) => results.extend(array),
+            (Segment::Wildcard, Value::Object(object)) => results.extend(object.values()),
+            (Segment::Filter(filter), Value::Array(array)) => {
+                results.extend(array.iter().filter(|item| filter.matches(item)))
+            }
+            (Segment::Filter(filter), Value::Object(object)) => {
+                results.extend(object.values().filter(|item| filter.matches(item)))
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, item: &Value) -> bool {
+        let values = self.path.query(item);
+        match &self.condition {
+            Some((operator, expected)) => values
+                .iter()
+                .any(|value| compare(value, *operator, expected)),
+            None => values
+                .iter()
+                .any(|value| !matches!(value, Value::Null | Value::Bool(false))),
+        }
+    }
+}
+
+fn compare(value: &Value, operator: Operator, expected: &Value) -> bool {
+    let ordering = match (value, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    match operator {
+        Operator::Equal => ordering.map_or(value == expected, |o| o == Ordering::Equal),
+        Operator::NotEqual => ordering.map_or(value != expected, |o| o != Ordering::Equal),
+        Operator::Less => ordering == Some(Ordering::Less),
+        Operator::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::Greater => ordering == Some(Ordering::Greater),
+        Operator::GreaterOrEqual => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+    }
+}
+
+/// Finds the `]` closing a bracket, skipping the ones inside quotes or parentheses.
+fn find_closing_bracket(content: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut depth = 0;
+    for (index, character) in content.char_indices() {
+        match (quote, character) {
+            (Some(open), character) if character == open => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(character),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ']') if depth == 0 => return Some(index),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+fn parse_bracket(content: &str) -> Result<Segment> {
+    if content == "*" {
+        return Ok(Segment::Wildcard);
+    }
+    if let Ok(index) = content.parse::<usize>() {
+        return Ok(Segment::Index(index));
+    }
+    if let Some(key) = strip_quotes(content) {
+        return Ok(Segment::Key(key.to_owned()));
+    }
+    if let Some(filter) = content
+        .strip_prefix("?(")
+        .and_then(|filter| filter.strip_suffix(')'))
+    {
+        return Ok(Segment::Filter(parse_filter(filter.trim())?));
+    }
+
+    bail!("\"[{}]\" is not a valid selector", content)
+}
+
+fn parse_filter(filter: &str) -> Result<Filter> {
+    let (path, condition) = match OPERATORS
+        .iter()
+        .filter_map(|(symbol, operator)| Some((filter.find(symbol)?, *symbol, *operator)))
+        .min_by_key(|(position, symbol, _)| (*position, usize::MAX - symbol.len()))
+    {
+        Some((position, symbol, operator)) => {
+            let literal = filter[position + symbol.len()..].trim();
+            let expected = match strip_quotes(literal) {
+                Some(string) => Value::String(string.to_owned()),
+                None => serde_json::from_str(literal)
+                    .map_err(|_| anyhow!("\"{}\" is not a valid value", literal))?,
+            };
+            (filter[..position].trim(), Some((operator, expected)))
+        }
+        None => (filter, None),
+    };
+
+    let path = match path.strip_prefix('@') {
+        Some(path) => JsonPath::parse(path)?,
+        None => bail!("filter \"{}\" must start with @", filter),
+    };
+
+    Ok(Filter { path, condition })
+}
+
+fn strip_quotes(string: &str) -> Option<&str> {
+    string
+        .strip_prefix('\'')
+        .and_then(|string| string.strip_suffix('\''))
+        .or_else(|| {
+            string
+                .strip_prefix('"')
+                .and_then(|string| string.strip_suffix('"'))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::JsonPath;
+
+    fn query(value: &Value, path: &str) -> Vec<Value> {
+        JsonPath::parse(path)
+            .unwrap()
+            .query(value)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_json_path() {
+        let json = json!({
+            "groups": [
+                { "chapters": [{ "id": 1, "readable": true }, { "id": 2, "readable": false }] },
+                { "chapters": [{ "id": 3, "readable": true, "title": "Three" }] },
+            ]
+        });
+
+        // Plain dot paths keep working, numeric keys index arrays
+        assert_eq!(query(&json, "groups.0.chapters.1.id"), vec![json!(2)]);
+        assert_eq!(
+            query(&json, "$.groups[1]['chapters'][0].id"),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            query(&json, "groups[*].chapters[*].id"),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(
+            query(&json, "groups[*].chapters[?(@.readable == true)].id"),
+            vec![json!(1), json!(3)]
+        );
+        assert_eq!(
+            query(&json, "groups[*].chapters[?(@.id >= 2)].id"),
+            vec![json!(2), json!(3)]
+        );
+        assert_eq!(
+            query(&json, "groups[*].chapters[?(@.title)].id"),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            query(&json, "groups[*].chapters[?(@.id != 2)].id"),
+            vec![json!(1), json!(3)]
+        );
+        assert!(query(&json, "groups.5.chapters").is_empty());
+
+        assert!(JsonPath::parse("groups[").is_err());
+        assert!(JsonPath::parse("groups..chapters").is_err());
+        assert!(JsonPath::parse("groups[?(readable == true)]").is_err());
+    }
+}
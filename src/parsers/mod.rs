@@ -1,5 +1,17 @@
-pub mod html;
-pub mod json;
-pub mod json_in_html;
-pub mod rss;
-pub mod utils;
+pub mod condition;
+pub mod dates;
+pub mod detail;
+pub mod html;
+pub mod json;
+pub mod json_in_html;
+pub mod json_path;
+pub mod pagination;
+pub mod parser;
+pub mod readers;
+pub mod rss;
+pub mod script;
+pub mod script_data;
+pub mod template;
+pub mod transform;
+pub mod utils;
+pub mod xml;
@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Result};
+use scraper::Html;
+use serde_json::Value;
+use url::Url;
+
+use crate::structs::{PageStrategy, TargetPagination};
+
+use super::html::{get_value, make_selector};
+
+/// Gets the URL of the first page to fetch.
+pub fn get_first_page_url(source: &str, pagination: &TargetPagination) -> Result<String> {
+    match &pagination.strategy {
+        PageStrategy::Number { parameter, start } => {
+            set_query_parameter(source, parameter, &start.to_string())
+        }
+        _ => Ok(source.to_owned()),
+    }
+}
+
+/// Gets the URL of the page after the `fetched` pages, the last of which is at `url` with `body`.
+/// Returns `None` if there is no next page.
+pub fn get_next_page_url(
+    source: &str,
+    pagination: &TargetPagination,
+    fetched: u32,
+    url: &str,
+    body: &str,
+) -> Result<Option<String>> {
+    match &pagination.strategy {
+        PageStrategy::Number { parameter, start } => {
+            let page = start + fetched;
+            Ok(Some(set_query_parameter(
+                source,
+                parameter,
+                &page.to_string(),
+            )?))
+        }
+        PageStrategy::NextLink { tag, attribute } => {
+            let html = Html::parse_document(body);
+            let selector = make_selector(tag)?;
+            let link = html
+                .select(&selector)
+                .next()
+                .and_then(|element| get_value(&element, &None, &Some(attribute.to_owned())).ok())
+                .map(|link| link.trim().to_owned())
+                .filter(|link| !link.is_empty());
+
+            match link {
+                Some(link) => Ok(Some(Url::parse(url)?.join(&link)?.into())),
+                None => Ok(None),
+            }
+        }
+        PageStrategy::Cursor { path, parameter } => {
+            let json: Value = serde_json::from_str(body)?;
+            let cursor = match path.get(&json) {
+                Some(Value::String(cursor)) => cursor.to_owned(),
+                Some(Value::Number(cursor)) => cursor.to_string(),
+                Some(Value::Null) | None => return Ok(None),
+                Some(_) => return Err(anyhow!("Cursor at {} isn't a string or number", path)),
+            };
+            if cursor.is_empty() {
+                return Ok(None);
+            }
+
+            match parameter {
+                Some(parameter) => Ok(Some(set_query_parameter(source, parameter, &cursor)?)),
+                None => Ok(Some(Url::parse(url)?.join(&cursor)?.into())),
+            }
+        }
+    }
+}
+
+/// Sets a query parameter of a URL, replacing its current value if it already has one.
+fn set_query_parameter(url: &str, parameter: &str, value: &str) -> Result<String> {
+    let mut url = Url::parse(url)?;
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != parameter)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(parameter, value);
+
+    Ok(url.into())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        parsers::json_path::JsonPath,
+        structs::{PageStrategy, TargetPagination},
+    };
+
+    use super::{get_first_page_url, get_next_page_url};
+
+    #[test]
+    fn test_get_page_urls() {
+        let source = "https://championcross.jp/series/899dda204c3f2/pagingList?s=2&page=0&limit=30";
+        let mut pagination = TargetPagination {
+            strategy: PageStrategy::Number {
+                parameter: "page".into(),
+                start: 1,
+            },
+            max_pages: 10,
+            stop_on_known: false,
+        };
+        assert_eq!(
+            get_first_page_url(source, &pagination).unwrap(),
+            "https://championcross.jp/series/899dda204c3f2/pagingList?s=2&limit=30&page=1",
+        );
+        assert_eq!(
+            get_next_page_url(source, &pagination, 1, source, "").unwrap(),
+            Some(
+                "https://championcross.jp/series/899dda204c3f2/pagingList?s=2&limit=30&page=2"
+                    .into()
+            ),
+        );
+
+        pagination.strategy = PageStrategy::NextLink {
+            tag: "a[rel=next]".into(),
+            attribute: "href".into(),
+        };
+        let url = "https://comic-html.com/series/1?page=2";
+        let body = r#"<html><body><a rel="next" href="?page=3">Next</a></body></html>"#;
+        assert_eq!(
+            get_next_page_url(url, &pagination, 2, url, body).unwrap(),
+            Some("https://comic-html.com/series/1?page=3".into()),
+        );
+        assert_eq!(
+            get_next_page_url(url, &pagination, 2, url, "<html></html>").unwrap(),
+            None,
+        );
+
+        pagination.strategy = PageStrategy::Cursor {
+            path: JsonPath::parse("data.next_cursor").unwrap(),
+            parameter: Some("cursor".into()),
+        };
+        let source = "https://comic-json.com/api/episodes?order=desc";
+        let body = r#"{ "data": { "episodes": [], "next_cursor": "abc" } }"#;
+        assert_eq!(
+            get_next_page_url(source, &pagination, 1, source, body).unwrap(),
+            Some("https://comic-json.com/api/episodes?order=desc&cursor=abc".into()),
+        );
+        let body = r#"{ "data": { "episodes": [], "next_cursor": null } }"#;
+        assert_eq!(
+            get_next_page_url(source, &pagination, 1, source, body).unwrap(),
+            None,
+        );
+    }
+}
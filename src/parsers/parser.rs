@@ -0,0 +1,115 @@
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+
+use crate::{
+    config::reader::TableReader,
+    structs::{Chapter, ParseRules, Target},
+};
+
+use super::{
+    html::HtmlParser, json::JsonParser, json_in_html::JsonInHtmlParser, rss::RssParser,
+    script::ScriptParser, xml::XmlParser,
+};
+
+/// Reads the chapters of a source, picked by the `mode` of a target.
+/// Each parser owns the tables of the target it reads its rules from (like `keys` or `tags`),
+/// so a new mode only needs its own module and an entry in `PARSERS`.
+pub trait Parser: Send + Sync {
+    /// Value of `mode` that picks this parser.
+    fn name(&self) -> &'static str;
+
+    /// Tables of a target this parser reads its rules from.
+    fn tables(&self) -> &'static [&'static str];
+
+    /// Tables that have to be set in every target of this mode.
+    fn required_tables(&self) -> &'static [&'static str] {
+        self.tables()
+    }
+
+    /// JSON Schema of one of the tables of this parser.
+    fn table_schema(&self, table: &str) -> JsonValue;
+
+    /// Reads the rules from the tables of a target, reporting any problem to the reader.
+    /// `title` keys are optional when the target has a title template.
+    /// Returns `None` if any of them is invalid.
+    fn read_rules(&self, target: &TableReader, has_template: bool) -> Option<ParseRules>;
+
+    /// Keys of `pagination` picking the strategies this parser can follow,
+    /// besides `pageParameter` which works with any source.
+    fn page_strategies(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Keys of its tables holding the path of a file, as `(table, key)`.
+    /// Relative paths are read from the directory of the main config file,
+    /// and the files are watched for changes like the config files.
+    fn file_keys(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Whether the title template can use a field other than `number` and `title`.
+    fn has_field(&self, rules: &ParseRules, name: &str) -> bool;
+
+    /// Gets the chapters out of a response body.
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>>;
+}
+
+/// Every parse mode, in the order they are listed in error messages and the schema.
+pub static PARSERS: [&dyn Parser; 6] = [
+    &RssParser,
+    &JsonParser,
+    &HtmlParser,
+    &JsonInHtmlParser,
+    &XmlParser,
+    &ScriptParser,
+];
+
+/// Finds the parser of a mode by its name.
+pub fn find_parser(name: &str) -> Option<&'static dyn Parser> {
+    PARSERS.iter().copied().find(|parser| parser.name() == name)
+}
+
+/// Lists the names of every mode, like `"rss", "json" or "xml"`.
+pub fn describe_modes() -> String {
+    describe_modes_where(|_| true)
+}
+
+/// Lists the names of the modes whose parser passes the filter, like `"html" or "json_in_html"`.
+pub fn describe_modes_where(filter: impl Fn(&dyn Parser) -> bool) -> String {
+    let names: Vec<String> = PARSERS
+        .iter()
+        .filter(|parser| filter(**parser))
+        .map(|parser| format!("\"{}\"", parser.name()))
+        .collect();
+
+    match names.split_last() {
+        Some((last, [])) => last.to_owned(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{describe_modes, describe_modes_where, find_parser, PARSERS};
+
+    #[test]
+    fn test_find_parser() {
+        assert_eq!(find_parser("json_in_html").unwrap().name(), "json_in_html");
+        assert!(find_parser("JSON").is_none());
+        assert_eq!(
+            describe_modes(),
+            "\"rss\", \"json\", \"html\", \"json_in_html\", \"xml\" or \"script\""
+        );
+        assert_eq!(
+            describe_modes_where(|parser| parser.page_strategies().contains(&"nextTag")),
+            "\"html\" or \"json_in_html\""
+        );
+
+        for parser in PARSERS {
+            for table in parser.required_tables() {
+                assert!(parser.tables().contains(table), "{}", parser.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::{config::reader::TableReader, structs::JsonDateTimeFormat};
+
+use super::json_path::JsonPath;
+
+/// Reads the `dateFormat` of a JSON source.
+pub fn read_json_date_format(reader: &TableReader) -> Option<JsonDateTimeFormat> {
+    reader
+        .string("dateFormat")
+        .map(|date_format| JsonDateTimeFormat::parse(&date_format))
+}
+
+/// Parses a JSON path, reporting it under `key` when it is not valid.
+pub fn parse_json_path(reader: &TableReader, key: &str, path: &str) -> Option<JsonPath> {
+    match JsonPath::parse(path) {
+        Ok(path) => Some(path),
+        Err(error) => {
+            reader.error(
+                key,
+                format!("is not a valid JSON path (\"{}\": {})", path, error),
+            );
+            None
+        }
+    }
+}
+
+/// Parses every path, reporting each one that is not a valid JSON path.
+pub fn parse_json_paths(
+    reader: &TableReader,
+    key: &str,
+    paths: &[String],
+) -> Option<Vec<JsonPath>> {
+    let paths: Vec<_> = paths
+        .iter()
+        .map(|path| parse_json_path(reader, key, path))
+        .collect();
+    paths.into_iter().collect()
+}
+
+/// Reads an optional table of JSON paths, e.g. the named `fields` of the JSON keys.
+pub fn read_json_path_table(reader: &TableReader, key: &str) -> Option<HashMap<String, JsonPath>> {
+    let strings = reader.string_table(key)?;
+    let table = match reader.table(key) {
+        Some(table) => table,
+        None => return Some(HashMap::new()),
+    };
+
+    let paths: HashMap<_, _> = strings
+        .iter()
+        .filter_map(|(name, path)| Some((name.to_owned(), parse_json_path(&table, name, path)?)))
+        .collect();
+    (paths.len() == strings.len()).then_some(paths)
+}
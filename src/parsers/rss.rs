@@ -1,126 +1,509 @@
-use anyhow::Result;
-use chrono::{Duration, Utc};
-use feed_rs::{model::Link, parser};
-
-use crate::structs::{Chapter, Target};
-
-use super::utils::make_link;
-
-fn get_link_href(links: &[Link]) -> String {
-    links.first().unwrap().href.to_owned()
-}
-
-pub fn parse_rss(target: &Target, source: &str) -> Result<Vec<Chapter>> {
-    let feed = parser::parse(source.as_bytes()).unwrap();
-
-    let mut chapters: Vec<Chapter> = vec![];
-    for entry in feed.entries {
-        let link = get_link_href(&entry.links);
-
-        let date = entry.published.unwrap_or(Utc::now());
-
-        chapters.push(Chapter {
-            manga: target.name.to_owned(),
-            number: entry.id,
-            title: entry.title.unwrap().content,
-            date: entry.published.unwrap_or(Utc::now()),
-            url: match &target.base_url {
-                Some(base_url) => make_link(&base_url, &link),
-                None => link,
-            },
-            logged_at: None,
-            announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
-        })
-    }
-
-    if !target.ascending_source {
-        chapters.reverse();
-    }
-
-    Ok(chapters)
-}
-
-#[cfg(test)]
-mod test {
-    use chrono::DateTime;
-
-    use crate::structs::{ParseMode, Target};
-
-    use super::parse_rss;
-
-    #[test]
-    fn test_parse_rss() {
-        let target = Target {
-            name: "Test Manga".into(),
-            source: "https://comic-rss.com/test.rss".into(),
-            ascending_source: false,
-            mode: ParseMode::Rss,
-            base_url: Some("https://comic-rss.com".into()),
-            request_headers: None,
-            delay: None,
-            keys: None,
-            tags: None,
-        };
-
-        let source = r###"<?xml version="1.0"?>
-        <rss version="2.0" xmlns:giga="https://gigaviewer.com">
-            <channel>
-                <title>RSS Test Publishing</title>
-                <pubDate>Fri, 23 Sep 2022 03:00:00 +0000</pubDate>
-                <link>https://comic-rss.com/title/11111</link>
-                <description>Lorem ipsum</description>
-                <docs>http://blogs.law.harvard.edu/tech/rss</docs>
-                <item>
-                    <title>Part 24: The Omega</title>
-                    <link>https://comic-rss.com/episode/00024</link>
-                    <guid isPermalink="false">00024</guid>
-                    <pubDate>Fri, 23 Sep 2022 03:00:00 +0000</pubDate>
-                    <enclosure url="https://cdn-img.comic-rss.com/public/episode-thumbnail/123" length="0" type="image/jpeg" />
-                    <author>Noowee</author>
-                </item>
-                <item>
-                    <title>Part 23: The Alpha</title>
-                    <link>/episode/00023</link>
-                    <guid isPermalink="false">00023</guid>
-                    <pubDate>Fri, 16 Sep 2022 03:00:00 +0000</pubDate>
-                    <enclosure url="https://cdn-img.comic-rss.com/public/episode-thumbnail/321" length="0" type="image/jpeg" />
-                    <author>Noowee</author>
-                </item>
-            </channel>
-        </rss>"###;
-        let chapters = parse_rss(&target, source).unwrap();
-
-        // Should have 2 chapters
-        assert!(chapters.len() == 2);
-        // Check manga title
-        assert_eq!(chapters[0].manga, "Test Manga");
-        assert_eq!(chapters[0].manga, chapters[1].manga);
-        // Check numbers (chapter IDs)
-        assert_eq!(chapters[0].number, "00023");
-        assert_eq!(chapters[1].number, "00024");
-        // Check titles
-        assert_eq!(chapters[0].title, "Part 23: The Alpha");
-        assert_eq!(chapters[1].title, "Part 24: The Omega");
-        // Check links
-        assert_eq!(chapters[0].url, "https://comic-rss.com/episode/00023");
-        assert_eq!(chapters[1].url, "https://comic-rss.com/episode/00024");
-        // Check dates
-        assert_eq!(
-            chapters[0].date,
-            DateTime::parse_from_rfc2822("Fri, 16 Sep 2022 03:00:00 +0000").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].date,
-            DateTime::parse_from_rfc2822("Fri, 23 Sep 2022 03:00:00 +0000").unwrap(),
-        );
-        // Check announce time
-        assert_eq!(
-            chapters[0].date,
-            DateTime::parse_from_rfc2822("Fri, 16 Sep 2022 03:00:00 +0000").unwrap(),
-        );
-        assert_eq!(
-            chapters[1].date,
-            DateTime::parse_from_rfc2822("Fri, 23 Sep 2022 03:00:00 +0000").unwrap(),
-        );
-    }
-}
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use colored::Colorize;
+use feed_rs::{model::Entry, parser};
+use quick_xml::{events::Event, Reader};
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    config::reader::TableReader,
+    log,
+    structs::{Chapter, FeedField, ParseRules, Target, TargetFeed},
+};
+
+use super::{
+    parser::Parser,
+    transform::apply_transforms,
+    utils::{make_image_link, make_link, naive_to_utc},
+};
+
+/// Keys allowed in the `feed` table of a target.
+pub const TARGET_FEED_KEYS: [&str; 6] = [
+    "number",
+    "title",
+    "date",
+    "url",
+    "thumbnail",
+    "missingTitle",
+];
+
+/// Gets the text of every namespaced child element (e.g. `giga:episodeNumber`) of each entry,
+/// in the order the entries appear in the feed.
+fn get_entry_extensions(source: &str) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = Reader::from_str(source);
+    let mut entries = vec![];
+
+    // Depth of the current element inside an entry (0 when not inside one)
+    let mut depth = 0;
+    let mut current: Option<(String, String)> = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+                let local_name =
+                    String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                if depth == 0 && (local_name == "item" || local_name == "entry") {
+                    entries.push(HashMap::new());
+                    depth = 1;
+                } else if depth > 0 {
+                    depth += 1;
+                    if depth == 2 && name.contains(':') {
+                        current = Some((name, String::new()));
+                    }
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, value)) = &mut current {
+                    value.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some((_, value)) = &mut current {
+                    value.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(_) if depth > 0 => {
+                depth -= 1;
+                if depth == 1 {
+                    if let (Some((name, value)), Some(extensions)) =
+                        (current.take(), entries.last_mut())
+                    {
+                        extensions.entry(name).or_insert(value.trim().to_owned());
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Gets the value of a field from a feed entry, if present.
+fn get_field(
+    entry: &Entry,
+    extensions: Option<&HashMap<String, String>>,
+    field: &FeedField,
+) -> Option<String> {
+    let value = match field {
+        FeedField::Id => Some(entry.id.to_owned()),
+        FeedField::Title => entry.title.as_ref().map(|title| title.content.to_owned()),
+        FeedField::Summary => entry
+            .summary
+            .as_ref()
+            .map(|summary| summary.content.to_owned()),
+        FeedField::Content => entry
+            .content
+            .as_ref()
+            .and_then(|content| content.body.to_owned()),
+        FeedField::Link(rel) => entry
+            .links
+            .iter()
+            .find(|link| rel.is_none() || link.rel == *rel)
+            .map(|link| link.href.to_owned()),
+        FeedField::Enclosure => entry
+            .media
+            .iter()
+            .flat_map(|media| &media.content)
+            .find_map(|content| content.url.as_ref())
+            .map(|url| url.to_string()),
+        FeedField::Thumbnail => entry
+            .media
+            .iter()
+            .flat_map(|media| &media.thumbnails)
+            .map(|thumbnail| thumbnail.image.uri.to_owned())
+            .next(),
+        FeedField::Published => entry.published.map(|date| date.to_rfc3339()),
+        FeedField::Updated => entry.updated.map(|date| date.to_rfc3339()),
+        FeedField::Extension(name) => {
+            extensions.and_then(|extensions| extensions.get(name).cloned())
+        }
+    };
+
+    value.filter(|value| !value.is_empty())
+}
+
+/// Gets the value of the first field present in the entry.
+fn get_first_field(
+    entry: &Entry,
+    extensions: Option<&HashMap<String, String>>,
+    fields: &[FeedField],
+) -> Option<String> {
+    fields
+        .iter()
+        .find_map(|field| get_field(entry, extensions, field))
+}
+
+/// Parses an RFC 3339 or RFC 2822 date. Dates without an offset are read in the target's time zone.
+fn parse_date(date: &str, timezone: &Option<Tz>) -> Result<DateTime<Utc>> {
+    if let Ok(date) =
+        DateTime::parse_from_rfc3339(date).or_else(|_| DateTime::parse_from_rfc2822(date))
+    {
+        return Ok(date.into());
+    }
+
+    let naive_date = NaiveDateTime::from_str(date.trim())
+        .map_err(|_| anyhow!("Could not parse date {}", date))?;
+    naive_to_utc(&naive_date, timezone)
+}
+
+/// Gets the `feed` fields of a `rss` target.
+pub fn feed(rules: &ParseRules) -> Result<&TargetFeed> {
+    rules.get().ok_or(anyhow!("Target has no feed fields"))
+}
+
+pub fn parse_rss(target: &Target, source: &str) -> Result<Vec<Chapter>> {
+    let fields = feed(&target.rules)?;
+    let feed = parser::parse(source.as_bytes())?;
+
+    // Namespaced elements aren't kept by the feed parser, so they are read separately
+    let uses_extensions = [
+        &fields.number,
+        &fields.title,
+        &fields.date,
+        &fields.url,
+        &fields.thumbnail,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|field| matches!(field, FeedField::Extension(_)))
+        || target.title_template.as_ref().is_some_and(|template| {
+            template
+                .field_names()
+                .iter()
+                .any(|name| matches!(FeedField::parse(name), Some(FeedField::Extension(_))))
+        });
+    let extensions = match uses_extensions {
+        true => get_entry_extensions(source)?,
+        false => vec![],
+    };
+
+    let mut chapters: Vec<Chapter> = vec![];
+    for (index, entry) in feed.entries.iter().enumerate() {
+        let extensions = extensions.get(index);
+        let get = |fields: &[FeedField]| get_first_field(entry, extensions, fields);
+
+        let number = get(&fields.number).ok_or(anyhow!("Entry {} has no number", entry.id))?;
+        let title = match &target.title_template {
+            Some(template) => Some(template.render(|name| match name {
+                "number" => Some(number.to_owned()),
+                "title" => get(&fields.title),
+                name => FeedField::parse(name).and_then(|field| get(&[field])),
+            }))
+            .filter(|title| !title.is_empty()),
+            None => get(&fields.title),
+        };
+        let title = match title {
+            Some(title) => title,
+            None if fields.skip_untitled => {
+                log!(
+                    "{} {}: Skipping entry {} without a title.",
+                    "[GOFR]".green(),
+                    target.name,
+                    entry.id
+                );
+                continue;
+            }
+            None => bail!("Entry {} has no title", entry.id),
+        };
+        let date = match get(&fields.date) {
+            Some(date) => parse_date(&date, &target.timezone)?,
+            None => Utc::now(),
+        };
+        let link = get(&fields.url).ok_or(anyhow!("Entry {} has no URL", entry.id))?;
+        let thumbnail =
+            get(&fields.thumbnail).and_then(|link| make_image_link(&target.base_url, &link));
+
+        chapters.push(Chapter {
+            manga: target.name.to_owned(),
+            number,
+            title,
+            date,
+            url: match &target.base_url {
+                Some(base_url) => make_link(base_url, &link),
+                None => link,
+            },
+            thumbnail,
+            logged_at: None,
+            announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
+        })
+    }
+
+    apply_transforms(target, &mut chapters);
+
+    if !target.ascending_source {
+        chapters.reverse();
+    }
+
+    Ok(chapters)
+}
+
+/// Parser of `mode = "rss"`, for RSS and Atom feeds.
+/// Its optional `feed` table picks the feed fields of the chapter values.
+pub struct RssParser;
+
+impl Parser for RssParser {
+    fn name(&self) -> &'static str {
+        "rss"
+    }
+
+    fn tables(&self) -> &'static [&'static str] {
+        &["feed"]
+    }
+
+    fn required_tables(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn table_schema(&self, _table: &str) -> JsonValue {
+        feed_schema()
+    }
+
+    fn read_rules(&self, target: &TableReader, _has_template: bool) -> Option<ParseRules> {
+        let feed = match target.has("feed") {
+            true => read_feed(&target.table("feed")?)?,
+            false => TargetFeed::default(),
+        };
+
+        Some(ParseRules::new(feed))
+    }
+
+    // Any feed field can be used
+    fn has_field(&self, _rules: &ParseRules, name: &str) -> bool {
+        FeedField::parse(name).is_some()
+    }
+
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>> {
+        parse_rss(target, body)
+    }
+}
+
+/// Reads which feed fields provide the chapter values of a RSS/Atom target.
+/// Values that are not set keep their default field.
+pub fn read_feed(reader: &TableReader) -> Option<TargetFeed> {
+    reader.deny_unknown_keys(&TARGET_FEED_KEYS);
+
+    let default_feed = TargetFeed::default();
+    let fields = |key: &str, default: Vec<FeedField>| -> Option<Vec<FeedField>> {
+        if !reader.has(key) {
+            return Some(default);
+        }
+
+        let mut fields = vec![];
+        for name in reader.required_strings(key)? {
+            match FeedField::parse(&name) {
+                Some(field) => fields.push(field),
+                None => {
+                    reader.error(key, format!("has an unknown feed field \"{}\"", name));
+                    return None;
+                }
+            }
+        }
+        Some(fields)
+    };
+
+    let number = fields("number", default_feed.number);
+    let title = fields("title", default_feed.title);
+    let date = fields("date", default_feed.date);
+    let url = fields("url", default_feed.url);
+    let thumbnail = fields("thumbnail", default_feed.thumbnail);
+    let skip_untitled = match reader.string("missingTitle").as_deref() {
+        Some("skip") => Some(true),
+        Some("error") => Some(false),
+        Some(other) => {
+            reader.error(
+                "missingTitle",
+                format!("must be \"skip\" or \"error\" (found \"{}\")", other),
+            );
+            None
+        }
+        None if reader.has("missingTitle") => None,
+        None => Some(default_feed.skip_untitled),
+    };
+
+    Some(TargetFeed {
+        number: number?,
+        title: title?,
+        date: date?,
+        url: url?,
+        thumbnail: thumbnail?,
+        skip_untitled: skip_untitled?,
+    })
+}
+
+fn feed_schema() -> JsonValue {
+    let fields = json!({
+        "description": "Feed field, or a list of them where the first one present is used: id (guid), title, summary (description), content, link, link:<rel>, enclosure, thumbnail (media:thumbnail), published (pubDate), updated or a namespaced element such as giga:episodeNumber.",
+        "oneOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } },
+        ],
+    });
+
+    json!({
+        "type": "object",
+        "description": "Which feed fields provide the chapter values of a RSS/Atom source.",
+        "additionalProperties": false,
+        "properties": {
+            "number": fields.clone(),
+            "title": fields.clone(),
+            "date": fields.clone(),
+            "url": fields.clone(),
+            "thumbnail": fields,
+            "missingTitle": {
+                "enum": ["skip", "error"],
+                "description": "Whether entries without a title are skipped or fail the fetch.",
+                "default": "skip",
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::DateTime;
+
+    use crate::structs::{FeedField, ParseRules, Target, TargetFeed};
+
+    use super::{feed, parse_rss};
+
+    #[test]
+    fn test_parse_rss() {
+        let target = Target {
+            base_url: Some("https://comic-rss.com".into()),
+            ..Target::for_test("Test Manga", "https://comic-rss.com/test.rss")
+        };
+
+        let source = r###"<?xml version="1.0"?>
+        <rss version="2.0" xmlns:giga="https://gigaviewer.com">
+            <channel>
+                <title>RSS Test Publishing</title>
+                <pubDate>Fri, 23 Sep 2022 03:00:00 +0000</pubDate>
+                <link>https://comic-rss.com/title/11111</link>
+                <description>Lorem ipsum</description>
+                <docs>http://blogs.law.harvard.edu/tech/rss</docs>
+                <item>
+                    <title>Part 24: The Omega</title>
+                    <link>https://comic-rss.com/episode/00024</link>
+                    <guid isPermalink="false">00024</guid>
+                    <pubDate>Fri, 23 Sep 2022 03:00:00 +0000</pubDate>
+                    <enclosure url="https://cdn-img.comic-rss.com/public/episode-thumbnail/123" length="0" type="image/jpeg" />
+                    <author>Noowee</author>
+                </item>
+                <item>
+                    <title>Part 23: The Alpha</title>
+                    <link>/episode/00023</link>
+                    <guid isPermalink="false">00023</guid>
+                    <pubDate>Fri, 16 Sep 2022 03:00:00 +0000</pubDate>
+                    <enclosure url="https://cdn-img.comic-rss.com/public/episode-thumbnail/321" length="0" type="image/jpeg" />
+                    <author>Noowee</author>
+                </item>
+            </channel>
+        </rss>"###;
+        let chapters = parse_rss(&target, source).unwrap();
+
+        // Should have 2 chapters
+        assert!(chapters.len() == 2);
+        // Check manga title
+        assert_eq!(chapters[0].manga, "Test Manga");
+        assert_eq!(chapters[0].manga, chapters[1].manga);
+        // Check numbers (chapter IDs)
+        assert_eq!(chapters[0].number, "00023");
+        assert_eq!(chapters[1].number, "00024");
+        // Check titles
+        assert_eq!(chapters[0].title, "Part 23: The Alpha");
+        assert_eq!(chapters[1].title, "Part 24: The Omega");
+        // Check links
+        assert_eq!(chapters[0].url, "https://comic-rss.com/episode/00023");
+        assert_eq!(chapters[1].url, "https://comic-rss.com/episode/00024");
+        // Check thumbnails (from the enclosures)
+        assert_eq!(
+            chapters[0].thumbnail.as_deref(),
+            Some("https://cdn-img.comic-rss.com/public/episode-thumbnail/321"),
+        );
+        // Check dates
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc2822("Fri, 16 Sep 2022 03:00:00 +0000").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].date,
+            DateTime::parse_from_rfc2822("Fri, 23 Sep 2022 03:00:00 +0000").unwrap(),
+        );
+        // Check announce time
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc2822("Fri, 16 Sep 2022 03:00:00 +0000").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].date,
+            DateTime::parse_from_rfc2822("Fri, 23 Sep 2022 03:00:00 +0000").unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_parse_rss_feed_fields() {
+        let mut target = Target {
+            ascending_source: true,
+            rules: ParseRules::new(TargetFeed {
+                number: vec![
+                    FeedField::Extension("giga:episodeNumber".into()),
+                    FeedField::Id,
+                ],
+                title: vec![FeedField::Title],
+                date: vec![FeedField::Updated, FeedField::Published],
+                url: vec![FeedField::Enclosure],
+                thumbnail: vec![FeedField::Thumbnail],
+                skip_untitled: true,
+            }),
+            ..Target::for_test("Test Manga", "https://comic-rss.com/test.rss")
+        };
+
+        let source = r###"<?xml version="1.0"?>
+        <rss version="2.0" xmlns:giga="https://gigaviewer.com" xmlns:media="http://search.yahoo.com/mrss/">
+            <channel>
+                <title>RSS Test Publishing</title>
+                <link>https://comic-rss.com/title/11111</link>
+                <description>Lorem ipsum</description>
+                <item>
+                    <title>Part 23: The Alpha</title>
+                    <guid isPermalink="false">00023</guid>
+                    <pubDate>Fri, 16 Sep 2022 03:00:00 +0000</pubDate>
+                    <enclosure url="https://cdn-img.comic-rss.com/episode/321" length="0" type="image/jpeg" />
+                    <giga:episodeNumber><![CDATA[23]]></giga:episodeNumber>
+                    <media:thumbnail url="https://cdn-img.comic-rss.com/episode/321/thumbnail.jpg" />
+                </item>
+                <item>
+                    <guid isPermalink="false">00024</guid>
+                    <pubDate>Fri, 23 Sep 2022 03:00:00 +0000</pubDate>
+                    <enclosure url="https://cdn-img.comic-rss.com/episode/123" length="0" type="image/jpeg" />
+                </item>
+            </channel>
+        </rss>"###;
+        let chapters = parse_rss(&target, source).unwrap();
+
+        // The entry without a title is skipped
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].number, "23");
+        assert_eq!(chapters[0].url, "https://cdn-img.comic-rss.com/episode/321");
+        assert_eq!(
+            chapters[0].thumbnail.as_deref(),
+            Some("https://cdn-img.comic-rss.com/episode/321/thumbnail.jpg"),
+        );
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc2822("Fri, 16 Sep 2022 03:00:00 +0000").unwrap(),
+        );
+
+        target.rules = ParseRules::new(TargetFeed {
+            skip_untitled: false,
+            ..feed(&target.rules).unwrap().clone()
+        });
+        let error = parse_rss(&target, source).unwrap_err();
+        assert_eq!(error.to_string(), "Entry 00024 has no title");
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    collections::HashMap,
+    fs,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose, Engine as _};
+use colored::Colorize;
+use regex::Regex;
+use rhai::{module_resolvers::DummyModuleResolver, Array, Dynamic, Engine, Scope, AST};
+use serde_json::{json, Value};
+
+use crate::{
+    config::reader::TableReader,
+    log,
+    structs::{Chapter, ParseRules, Target, TargetKeys},
+};
+
+use super::{
+    json::parse_json_value,
+    json_path::JsonPath,
+    parser::Parser,
+    readers::{parse_json_paths, read_json_date_format},
+};
+
+/// Keys allowed in the `script` table of a target.
+pub const TARGET_SCRIPT_KEYS: [&str; 5] = ["code", "file", "dateFormat", "fields", "maxOperations"];
+
+/// Most operations a script can run when `maxOperations` is not set.
+const DEFAULT_MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Longest time a script can run, whatever its `maxOperations`.
+const TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// Longest string a script can build (response bodies can be large).
+const MAX_STRING_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest array or object map a script can build.
+const MAX_COLLECTION_SIZE: usize = 100_000;
+
+/// Deepest nesting of function calls in a script.
+const MAX_CALL_LEVELS: usize = 32;
+
+/// A compiled script, its limits and the fixed `keys` its chapters are read with.
+struct ScriptRules {
+    /// Source of the script, compared instead of the AST (recompiled on every reload).
+    code: String,
+    ast: AST,
+    max_operations: u64,
+    keys: TargetKeys,
+}
+
+impl PartialEq for ScriptRules {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+            && self.max_operations == other.max_operations
+            && self.keys == other.keys
+    }
+}
+
+/// Gets the script of a `script` target.
+fn script(rules: &ParseRules) -> Result<&ScriptRules> {
+    rules.get().ok_or(anyhow!("Target has no script"))
+}
+
+/// Makes a sandboxed engine: scripts can't import modules or read files,
+/// and are stopped when they run too many operations or for too long.
+fn make_engine(target_name: &str, max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(max_operations)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE);
+
+    let start = Instant::now();
+    engine.on_progress(move |_| {
+        (start.elapsed() > TIME_LIMIT).then(|| Dynamic::from("Script timed out"))
+    });
+
+    let name = target_name.to_owned();
+    engine.on_print(move |text| log!("{} {}: {}", "[GOFR]".green(), name, text));
+    let name = target_name.to_owned();
+    engine.on_debug(move |text, _, _| log!("{} {}: {}", "[GOFR]".green(), name, text));
+
+    engine
+        .register_fn("parse_json", parse_json)
+        .register_fn("decode_base64", decode_base64)
+        .register_fn("captures", captures);
+
+    engine
+}
+
+/// `parse_json(text)`: parses JSON of any kind, including arrays at the top level.
+fn parse_json(text: &str) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+    let json: Value = serde_json::from_str(text).map_err(|error| error.to_string())?;
+    rhai::serde::to_dynamic(json)
+}
+
+/// `decode_base64(text)`: decodes standard or URL-safe base64, with or without padding.
+fn decode_base64(text: &str) -> Result<String, Box<rhai::EvalAltResult>> {
+    let text = text.trim().trim_end_matches('=');
+    let bytes = general_purpose::STANDARD_NO_PAD
+        .decode(text)
+        .or_else(|_| general_purpose::URL_SAFE_NO_PAD.decode(text))
+        .map_err(|error| format!("Invalid base64 ({})", error))?;
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// `captures(text, pattern)`: the groups of every match of a regex,
+/// each as an array whose first item is the whole match. Missing groups are empty strings.
+fn captures(text: &str, pattern: &str) -> Result<Array, Box<rhai::EvalAltResult>> {
+    let regex = Regex::new(pattern).map_err(|error| format!("Invalid regex ({})", error))?;
+
+    Ok(regex
+        .captures_iter(text)
+        .map(|captures| {
+            let groups: Array = captures
+                .iter()
+                .map(|group| Dynamic::from(group.map_or("", |group| group.as_str()).to_owned()))
+                .collect();
+            Dynamic::from(groups)
+        })
+        .collect())
+}
+
+/// Runs the script of a target on a response body. It gets the body as `body` and the target's URL
+/// as `source`, and returns an array of chapters: `#{ number, title, date, url, thumbnail }`
+/// plus any of its `fields`. They are then read like the chapters of a `json` target.
+pub fn parse_script(target: &Target, source: &str) -> Result<Vec<Chapter>> {
+    let rules = script(&target.rules)?;
+
+    let engine = make_engine(&target.name, rules.max_operations);
+    let mut scope = Scope::new();
+    scope.push_constant("body", source.to_owned());
+    scope.push_constant("source", target.source.to_owned());
+
+    let result: Dynamic = engine
+        .eval_ast_with_scope(&mut scope, &rules.ast)
+        .map_err(|error| anyhow!("Script failed: {}", error))?;
+    if !result.is_array() {
+        bail!(
+            "Script must return an array of chapters (returned {})",
+            result.type_name()
+        );
+    }
+    let chapters: Value = rhai::serde::from_dynamic(&result)
+        .map_err(|error| anyhow!("Script returned invalid chapters: {}", error))?;
+
+    parse_json_value(target, &rules.keys, &json!({ "chapters": chapters }))
+}
+
+/// Parser of `mode = "script"`, running a Rhai script from its `script` table.
+pub struct ScriptParser;
+
+impl Parser for ScriptParser {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn tables(&self) -> &'static [&'static str] {
+        &["script"]
+    }
+
+    fn table_schema(&self, _table: &str) -> Value {
+        script_schema()
+    }
+
+    fn read_rules(&self, target: &TableReader, _has_template: bool) -> Option<ParseRules> {
+        let script = target.table("script")?;
+        read_script(&script)
+    }
+
+    fn file_keys(&self) -> &'static [(&'static str, &'static str)] {
+        &[("script", "file")]
+    }
+
+    fn has_field(&self, rules: &ParseRules, name: &str) -> bool {
+        script(rules).is_ok_and(|script| script.keys.fields.contains_key(name))
+    }
+
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>> {
+        parse_script(target, body)
+    }
+}
+
+/// Reads and compiles the script of a target, from either `code` or `file`.
+/// The chapters it returns are read with fixed `keys`, named after the chapter values.
+fn read_script(reader: &TableReader) -> Option<ParseRules> {
+    reader.deny_unknown_keys(&TARGET_SCRIPT_KEYS);
+
+    let key = match (reader.has("code"), reader.has("file")) {
+        (false, false) => {
+            reader.error("code", "is required (or file)");
+            return None;
+        }
+        (true, true) => {
+            reader.error("file", "cannot be used together with code");
+            return None;
+        }
+        (true, false) => "code",
+        (false, true) => "file",
+    };
+    let value = reader.string(key).filter(|value| {
+        if value.trim().is_empty() {
+            reader.error(key, "must not be empty");
+        }
+        !value.trim().is_empty()
+    });
+    let code = match key {
+        "code" => value,
+        _ => value.and_then(|path| match fs::read_to_string(&path) {
+            Ok(code) => Some(code),
+            Err(error) => {
+                reader.error(key, format!("could not be read ({})", error));
+                None
+            }
+        }),
+    };
+
+    let date_format = read_json_date_format(reader);
+    let fields = match reader.has("fields") {
+        true => reader
+            .required_strings("fields")
+            .and_then(|fields| parse_json_paths(reader, "fields", &fields)),
+        false => Some(vec![]),
+    };
+    let max_operations = match reader.has("maxOperations") {
+        true => reader.integer("maxOperations", 1, i64::MAX),
+        false => Some(DEFAULT_MAX_OPERATIONS as i64),
+    };
+
+    let ast = code.as_ref().and_then(|code| {
+        match make_engine("", DEFAULT_MAX_OPERATIONS).compile(code) {
+            Ok(ast) => Some(ast),
+            Err(error) => {
+                reader.error(key, format!("is not a valid script ({})", error));
+                None
+            }
+        }
+    });
+
+    let keys = TargetKeys {
+        chapters: JsonPath::key("chapters"),
+        number: vec![JsonPath::key("number")],
+        title: vec![JsonPath::key("title")],
+        date: JsonPath::key("date"),
+        date_format,
+        url: JsonPath::key("url"),
+        skip: vec![],
+        include: vec![],
+        thumbnail: Some(JsonPath::key("thumbnail")),
+        fields: fields?
+            .into_iter()
+            .map(|path| (path.to_string(), path))
+            .collect::<HashMap<_, _>>(),
+    };
+
+    Some(ParseRules::new(ScriptRules {
+        code: code?,
+        ast: ast?,
+        max_operations: max_operations? as u64,
+        keys,
+    }))
+}
+
+fn script_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Rhai script returning the chapters of the response body (in the body variable), like [#{ number: \"1\", title: \"Chapter 1\", date: 1698886800, url: \"/1\" }].",
+        "additionalProperties": false,
+        "oneOf": [
+            { "required": ["code"] },
+            { "required": ["file"] },
+        ],
+        "properties": {
+            "code": { "type": "string" },
+            "file": {
+                "type": "string",
+                "description": "Path of the script, relative to the settings file.",
+            },
+            "dateFormat": {
+                "type": "string",
+                "description": "\"unixsec\", \"unix\", \"unixmilli\", \"unixnano\", \"rfc2822\", \"rfc3339\", \"relative\" (e.g. 3日前, 2 hours ago) or a strftime format.",
+            },
+            "fields": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Extra values of the returned chapters, for titleTemplate.",
+            },
+            "maxOperations": {
+                "type": "integer",
+                "minimum": 1,
+                "default": DEFAULT_MAX_OPERATIONS,
+                "description": "Most operations the script can run before it's stopped.",
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+    use toml::Value as TomlValue;
+
+    use crate::config::{
+        reader::InvalidConfig,
+        reload::diff_targets,
+        settings::{get_config, get_config_files, get_settings, Settings},
+    };
+
+    use super::parse_script;
+
+    fn read_config(config: &str) -> Result<Settings> {
+        get_settings(&config.parse::<TomlValue>().unwrap())
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let config = r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            baseUrl = "https://comic.pixiv.net"
+            mode = "script"
+            titleTemplate = "{title}[ ({label})]"
+            [targets.script]
+            dateFormat = "unixsec"
+            fields = ["label"]
+            code = '''
+                let data = parse_json(body);
+                let chapters = [];
+                // Free and paid episodes are listed apart
+                for episode in data.free + data.paid {
+                    chapters.push(#{
+                        number: episode.id,
+                        title: decode_base64(episode.name),
+                        date: episode.time,
+                        url: `/viewer/${data.work}/${episode.id}`,
+                        label: if episode.price > 0 { "paid" } else { () },
+                    });
+                }
+                chapters
+            '''
+        "###;
+        let settings = read_config(config).unwrap();
+        let target = &settings.targets[0];
+
+        let body = r#"{
+            "work": 8789,
+            "free": [{ "id": 2, "name": "56ysMuipsQ", "time": 1698886800, "price": 0 }],
+            "paid": [{ "id": 3, "name": "56ysM-ipsQ==", "time": 1699491600, "price": 30 }]
+        }"#;
+        let chapters = parse_script(target, body).unwrap();
+        assert_eq!(chapters.len(), 2);
+        // Listed new chapters first
+        assert_eq!(chapters[0].number, "3");
+        assert_eq!(chapters[0].title, "第3話 (paid)");
+        assert_eq!(chapters[0].url, "https://comic.pixiv.net/viewer/8789/3");
+        assert_eq!(chapters[0].date.timestamp(), 1699491600);
+        assert_eq!(chapters[1].title, "第2話");
+
+        // Chapters missing a value fail like any other mode
+        let error = parse_script(
+            target,
+            r#"{ "work": 1, "free": [{ "id": 1 }], "paid": [] }"#,
+        );
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_script_limits() {
+        let config = r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "script"
+            [targets.script]
+            maxOperations = 1000
+            code = "let i = 0; loop { i += 1; }"
+        "###;
+        let settings = read_config(config).unwrap();
+        let error = parse_script(&settings.targets[0], "").unwrap_err();
+        assert!(
+            error.to_string().contains("Too many operations"),
+            "{}",
+            error
+        );
+
+        // Scripts can't load modules
+        let config = config.replace(
+            "let i = 0; loop { i += 1; }",
+            "import `/etc/passwd` as x; []",
+        );
+        let settings = read_config(&config).unwrap();
+        let error = parse_script(&settings.targets[0], "").unwrap_err();
+        assert!(error.to_string().contains("Module not found"), "{}", error);
+
+        // Syntax errors are found when reading the settings
+        let config = config.replace("import `/etc/passwd` as x; []", "let = 1");
+        let error = read_config(&config).unwrap_err();
+        let errors = &error.downcast_ref::<InvalidConfig>().unwrap().errors;
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(
+            errors[0].contains("script.code is not a valid script"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_script_file() {
+        let directory = std::env::temp_dir().join(format!("dmt-script-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("scripts")).unwrap();
+
+        let settings_path = directory.join("settings.toml");
+        let script_path = directory.join("scripts/chapters.rhai");
+        fs::write(
+            &settings_path,
+            r###"
+            token = ""
+
+            [[targets]]
+            name = "Kusunoki Debut"
+            source = "https://comic.pixiv.net/api/app/works/8789/episodes"
+            mode = "script"
+            [targets.script]
+            file = "scripts/chapters.rhai"
+            "###,
+        )
+        .unwrap();
+        fs::write(&script_path, "parse_json(body).chapters").unwrap();
+        let filename = settings_path.to_str().unwrap();
+        let load = || get_settings(&get_config(Some(filename)).unwrap()).unwrap();
+
+        // Read from the directory of the settings file, not the working directory
+        let old = load();
+        assert_eq!(
+            get_config_files(filename).unwrap(),
+            vec![settings_path.clone(), script_path.clone()]
+        );
+
+        // Compiling the same script again is not a change
+        assert!(diff_targets(&old.targets, &load().targets).is_empty());
+
+        fs::write(&script_path, "parse_json(body).episodes").unwrap();
+        assert_eq!(
+            diff_targets(&old.targets, &load().targets).changed,
+            ["Kusunoki Debut"]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
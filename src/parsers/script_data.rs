@@ -0,0 +1,358 @@
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+
+use crate::structs::ScriptLocator;
+
+/// Parses JSON, falling back to JSON5 (unquoted keys, single quotes, trailing commas, comments).
+pub fn parse_json_text(text: &str) -> Result<Value> {
+    match serde_json::from_str(text) {
+        Ok(json) => Ok(json),
+        Err(_) => json5::from_str(text).map_err(|error| anyhow!("Invalid JSON ({})", error)),
+    }
+}
+
+/// Gets the data that follows the marker or pattern in the text of a script.
+/// Returns `None` if the text doesn't contain the marker or pattern.
+pub fn extract_script_data(text: &str, locator: &ScriptLocator) -> Option<Result<Value>> {
+    let start = match locator {
+        ScriptLocator::Marker(marker) => text.find(marker.as_str())? + marker.len(),
+        ScriptLocator::Pattern(pattern) => {
+            let captures = pattern.captures(text)?;
+            match captures.get(1) {
+                Some(group) => group.start(),
+                None => captures.get(0)?.end(),
+            }
+        }
+    };
+
+    Some(read_data(&text[start..]))
+}
+
+/// Reads the value at the start of some JavaScript: an object or array literal,
+/// or a function called right away that returns one (like Nuxt's `window.__NUXT__`).
+fn read_data(code: &str) -> Result<Value> {
+    let code =
+        code.trim_start_matches(|character: char| character.is_whitespace() || character == '=');
+    if code.starts_with('{') || code.starts_with('[') {
+        let literal = take_balanced(code)?;
+        return parse_json_text(&replace_identifiers(literal, &[], &[])?);
+    }
+
+    let function = code.trim_start_matches('(').trim_start();
+    if function.starts_with("function") {
+        return read_function_data(function);
+    }
+
+    bail!("No object literal found in the script")
+}
+
+/// Reads `function(a, b) { ...; return {...} }(1, "x")`,
+/// putting the arguments in place of the parameters in the returned literal.
+fn read_function_data(function: &str) -> Result<Value> {
+    let open = function
+        .find('(')
+        .ok_or(anyhow!("Function without parameters"))?;
+    let parameters = take_balanced(&function[open..])?;
+    let rest = &function[open + parameters.len()..];
+    let parameters: Vec<&str> = parameters[1..parameters.len() - 1]
+        .split(',')
+        .map(str::trim)
+        .filter(|parameter| !parameter.is_empty())
+        .collect();
+
+    let body_start = rest.find('{').ok_or(anyhow!("Function without a body"))?;
+    let body = take_balanced(&rest[body_start..])?;
+
+    let after = &rest[body_start + body.len()..];
+    let arguments = match after.find('(') {
+        Some(start) => {
+            let arguments = take_balanced(&after[start..])?;
+            let arguments = replace_identifiers(&arguments[1..arguments.len() - 1], &[], &[])?;
+            match parse_json_text(&format!("[{}]", arguments))? {
+                Value::Array(arguments) => arguments,
+                _ => unreachable!(),
+            }
+        }
+        None => vec![],
+    };
+
+    let returned = find_returned_literal(body)?;
+    parse_json_text(&replace_identifiers(returned, &parameters, &arguments)?)
+}
+
+/// Finds the object or array literal returned by a function body.
+fn find_returned_literal(body: &str) -> Result<&str> {
+    let mut tokens = Tokens::new(body);
+    while let Some(token) = tokens.next().transpose()? {
+        if token.kind == TokenKind::Word && &body[token.start..token.end] == "return" {
+            let rest = body[token.end..].trim_start();
+            if rest.starts_with('{') || rest.starts_with('[') {
+                return take_balanced(rest);
+            }
+        }
+    }
+
+    bail!("Function does not return an object literal")
+}
+
+/// Takes the code from the opening bracket it starts with up to the matching closing bracket.
+fn take_balanced(code: &str) -> Result<&str> {
+    let mut depth = 0;
+    for token in Tokens::new(code) {
+        let token = token?;
+        if token.kind != TokenKind::Symbol {
+            continue;
+        }
+        match &code[token.start..token.end] {
+            "{" | "[" | "(" => depth += 1,
+            "}" | "]" | ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(&code[..token.end]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    bail!("Unbalanced brackets in the script")
+}
+
+/// Rewrites the JavaScript values JSON5 doesn't know (`void 0`, `undefined`, `!0`, `!1`)
+/// and puts the arguments in place of the parameter names.
+fn replace_identifiers(literal: &str, parameters: &[&str], arguments: &[Value]) -> Result<String> {
+    let tokens = Tokens::new(literal).collect::<Result<Vec<_>>>()?;
+    let text = |index: usize| {
+        tokens
+            .get(index)
+            .map(|token: &Token| &literal[token.start..token.end])
+    };
+
+    let mut output = String::new();
+    let mut previous_kind = None;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        let current = &literal[token.start..token.end];
+        let replacement = match (token.kind, current) {
+            // Keys are left as is
+            (TokenKind::Word, _) if text(index + 1) == Some(":") => None,
+            (TokenKind::Word, "void") => {
+                index += 1;
+                Some(String::from("null"))
+            }
+            (TokenKind::Word, "undefined") => Some(String::from("null")),
+            (TokenKind::Symbol, "!") if matches!(text(index + 1), Some("0") | Some("1")) => {
+                index += 1;
+                Some(String::from(match text(index) {
+                    Some("0") => "true",
+                    _ => "false",
+                }))
+            }
+            (TokenKind::Word, word) => parameters
+                .iter()
+                .position(|parameter| *parameter == word)
+                .map(|position| arguments.get(position).unwrap_or(&Value::Null).to_string()),
+            _ => None,
+        };
+
+        // Only adjacent words need a space between them, `-1` has to stay `-1`
+        if token.kind == TokenKind::Word && previous_kind == Some(TokenKind::Word) {
+            output.push(' ');
+        }
+        output.push_str(replacement.as_deref().unwrap_or(current));
+        previous_kind = Some(token.kind);
+        index += 1;
+    }
+
+    Ok(output)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    /// A quoted string, quotes included.
+    String,
+    /// An identifier, keyword or number.
+    Word,
+    Symbol,
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+/// A rough JavaScript tokenizer, enough to find brackets and identifiers outside of strings and comments.
+struct Tokens<'a> {
+    code: &'a str,
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(code: &'a str) -> Self {
+        Self { code, position: 0 }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<()> {
+        loop {
+            let rest = &self.code[self.position..];
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                let end = trimmed
+                    .find("*/")
+                    .ok_or(anyhow!("Unterminated comment in the script"))?;
+                self.position += end + 2;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(error) = self.skip_whitespace_and_comments() {
+            self.position = self.code.len();
+            return Some(Err(error));
+        }
+
+        let start = self.position;
+        let mut characters = self.code[start..].char_indices();
+        let (_, first) = characters.next()?;
+
+        let (kind, length) = match first {
+            '"' | '\'' | '`' => {
+                let mut escaped = false;
+                let end = characters.find(|(_, character)| {
+                    let closes = !escaped && *character == first;
+                    escaped = !escaped && *character == '\\';
+                    closes
+                });
+                match end {
+                    Some((end, _)) => (TokenKind::String, end + 1),
+                    None => {
+                        self.position = self.code.len();
+                        return Some(Err(anyhow!("Unterminated string in the script")));
+                    }
+                }
+            }
+            first if first.is_alphanumeric() || first == '_' || first == '$' || first == '.' => {
+                let mut previous = first;
+                let end = characters
+                    .find(|(_, character)| {
+                        let is_word = character.is_alphanumeric()
+                            || *character == '_'
+                            || *character == '$'
+                            // Decimal points and exponents of numbers
+                            || (*character == '.' && first.is_ascii_digit())
+                            || ((*character == '-' || *character == '+')
+                                && first.is_ascii_digit()
+                                && (previous == 'e' || previous == 'E'));
+                        previous = *character;
+                        !is_word
+                    })
+                    .map(|(end, _)| end)
+                    .unwrap_or(self.code.len() - start);
+                match first == '.' && end == 1 {
+                    true => (TokenKind::Symbol, 1),
+                    false => (TokenKind::Word, end),
+                }
+            }
+            symbol => (TokenKind::Symbol, symbol.len_utf8()),
+        };
+
+        self.position = start + length;
+        Some(Ok(Token {
+            kind,
+            start,
+            end: start + length,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::json;
+
+    use crate::structs::ScriptLocator;
+
+    use super::extract_script_data;
+
+    #[test]
+    fn test_extract_script_data() {
+        // Assignment with JSON5 (unquoted keys, single quotes, trailing commas, comments)
+        let script = r#"
+            window.dataLayer = [];
+            window.__INITIAL_STATE__ = {
+              episodes: [
+                { id: 2, title: 'Chapter 2 {part 1}', }, // Latest
+                { id: 1, title: "Chapter 1 \"}\"", },
+              ],
+            };
+            window.__OTHER__ = { ignored: true };
+        "#;
+        let locator = ScriptLocator::Marker("window.__INITIAL_STATE__".into());
+        let data = extract_script_data(script, &locator).unwrap().unwrap();
+        assert_eq!(
+            data,
+            json!({ "episodes": [
+                { "id": 2, "title": "Chapter 2 {part 1}" },
+                { "id": 1, "title": "Chapter 1 \"}\"" },
+            ] }),
+        );
+
+        // Capture group of a pattern
+        let script = r#"self.__next_f = 1; var state = JSON.parse('x'); const data = {"id": 5};"#;
+        let locator = ScriptLocator::Pattern(Regex::new(r"const data\s*=\s*(\{)").unwrap());
+        let data = extract_script_data(script, &locator).unwrap().unwrap();
+        assert_eq!(data, json!({ "id": 5 }));
+
+        // Not in this script
+        let locator = ScriptLocator::Marker("window.__NUXT__".into());
+        assert!(extract_script_data(script, &locator).is_none());
+
+        // Negative numbers
+        let script = r#"window.__INITIAL_STATE__ = { offset: -1, ratio: -0.5, ids: [-2, 3] };"#;
+        let locator = ScriptLocator::Marker("window.__INITIAL_STATE__".into());
+        let data = extract_script_data(script, &locator).unwrap().unwrap();
+        assert_eq!(data, json!({ "offset": -1, "ratio": -0.5, "ids": [-2, 3] }));
+    }
+
+    #[test]
+    fn test_extract_nuxt_negative_arguments() {
+        let script = r#"window.__NUXT__=(function(a,b){return {offset:a,chapters:[{id:b,order:-3}]}}(-1,-20));"#;
+        let locator = ScriptLocator::Marker("window.__NUXT__=".into());
+        let data = extract_script_data(script, &locator).unwrap().unwrap();
+        assert_eq!(
+            data,
+            json!({ "offset": -1, "chapters": [{ "id": -20, "order": -3 }] })
+        );
+    }
+
+    #[test]
+    fn test_extract_nuxt_data() {
+        let script = r#"window.__NUXT__=(function(a,b,c,d){return {layout:"default",data:[{episodes:[{id:a,title:"第2話",date:c,free:!0},{id:b,title:"第1話",date:c,free:!1,note:d}]}],state:{user:void 0}}}(102,101,"2023-11-02",null));"#;
+        let locator = ScriptLocator::Marker("window.__NUXT__=".into());
+        let data = extract_script_data(script, &locator).unwrap().unwrap();
+        assert_eq!(
+            data,
+            json!({
+                "layout": "default",
+                "data": [{ "episodes": [
+                    { "id": 102, "title": "第2話", "date": "2023-11-02", "free": true },
+                    { "id": 101, "title": "第1話", "date": "2023-11-02", "free": false, "note": null },
+                ] }],
+                "state": { "user": null },
+            }),
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{bail, Result};
+
+/// A chapter title format such as `{number}[: {subtitle}]`.
+///
+/// `{name}` is replaced by the value of a field. A section between `[` and `]` is left out
+/// entirely if any field inside it is missing or empty. `\{`, `\[` (etc.) write the character as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Field(String),
+    Optional(Vec<TemplatePart>),
+}
+
+impl TitleTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut stack: Vec<Vec<TemplatePart>> = vec![vec![]];
+        let mut text = String::new();
+        let mut characters = template.chars();
+
+        while let Some(character) = characters.next() {
+            match character {
+                '\\' => match characters.next() {
+                    Some(escaped) => text.push(escaped),
+                    None => bail!("ends with a lone \\"),
+                },
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match characters.next() {
+                            Some('}') => break,
+                            Some(character) => name.push(character),
+                            None => bail!("has a {{ without a closing }}"),
+                        }
+                    }
+                    let name = name.trim();
+                    if name.is_empty() {
+                        bail!("has an empty {{}}");
+                    }
+
+                    let parts = stack.last_mut().unwrap();
+                    flush_text(&mut text, parts);
+                    parts.push(TemplatePart::Field(name.to_owned()));
+                }
+                '}' => bail!("has a }} without an opening {{"),
+                '[' => {
+                    flush_text(&mut text, stack.last_mut().unwrap());
+                    stack.push(vec![]);
+                }
+                ']' => {
+                    if stack.len() == 1 {
+                        bail!("has a ] without an opening [");
+                    }
+                    let mut section = stack.pop().unwrap();
+                    flush_text(&mut text, &mut section);
+                    stack
+                        .last_mut()
+                        .unwrap()
+                        .push(TemplatePart::Optional(section));
+                }
+                character => text.push(character),
+            }
+        }
+
+        if stack.len() > 1 {
+            bail!("has a [ without a closing ]");
+        }
+        let mut parts = stack.pop().unwrap();
+        flush_text(&mut text, &mut parts);
+
+        Ok(Self { parts })
+    }
+
+    /// Names of every field used in the template.
+    pub fn field_names(&self) -> Vec<&str> {
+        let mut names = vec![];
+        collect_field_names(&self.parts, &mut names);
+        names
+    }
+
+    /// Fills in the template. `get_field` returns `None` for missing fields,
+    /// which are left blank outside of optional sections.
+    pub fn render(&self, get_field: impl Fn(&str) -> Option<String>) -> String {
+        render_parts(&self.parts, &get_field).0
+    }
+}
+
+fn flush_text(text: &mut String, parts: &mut Vec<TemplatePart>) {
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(std::mem::take(text)));
+    }
+}
+
+fn collect_field_names<'a>(parts: &'a [TemplatePart], names: &mut Vec<&'a str>) {
+    for part in parts {
+        match part {
+            TemplatePart::Text(_) => (),
+            TemplatePart::Field(name) => names.push(name),
+            TemplatePart::Optional(section) => collect_field_names(section, names),
+        }
+    }
+}
+
+/// Renders a list of parts, also returning whether every field in them had a value.
+fn render_parts(
+    parts: &[TemplatePart],
+    get_field: &impl Fn(&str) -> Option<String>,
+) -> (String, bool) {
+    let mut rendered = String::new();
+    let mut complete = true;
+    for part in parts {
+        match part {
+            TemplatePart::Text(text) => rendered.push_str(text),
+            TemplatePart::Field(name) => match get_field(name).filter(|value| !value.is_empty()) {
+                Some(value) => rendered.push_str(&value),
+                None => complete = false,
+            },
+            TemplatePart::Optional(section) => {
+                let (section, section_complete) = render_parts(section, get_field);
+                if section_complete {
+                    rendered.push_str(&section);
+                }
+            }
+        }
+    }
+
+    (rendered, complete)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::TitleTemplate;
+
+    #[test]
+    fn test_title_template() {
+        let template = TitleTemplate::parse("{number}[: {subtitle}][ ({volume}\\])]").unwrap();
+        assert_eq!(template.field_names(), vec!["number", "subtitle", "volume"]);
+
+        let fields = HashMap::from([("number", "第12話"), ("subtitle", "再会"), ("volume", "")]);
+        let get_field = |name: &str| fields.get(name).map(|value| value.to_string());
+        assert_eq!(template.render(get_field), "第12話: 再会");
+
+        let fields = HashMap::from([("number", "第13話"), ("volume", "3")]);
+        let get_field = |name: &str| fields.get(name).map(|value| value.to_string());
+        assert_eq!(template.render(get_field), "第13話 (3])");
+
+        assert!(TitleTemplate::parse("{number").is_err());
+        assert!(TitleTemplate::parse("{number}[: {subtitle}").is_err());
+        assert!(TitleTemplate::parse("{number}]").is_err());
+    }
+}
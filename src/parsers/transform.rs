@@ -0,0 +1,107 @@
+use crate::structs::{Chapter, ChapterField, Target, TransformStep};
+
+/// Runs the target's post-processing steps on the number, title and URL of every chapter.
+/// `From` steps read the values as they were extracted, before any other step ran.
+pub fn apply_transforms(target: &Target, chapters: &mut [Chapter]) {
+    let transforms = &target.transforms;
+    if transforms.number.is_empty() && transforms.title.is_empty() && transforms.url.is_empty() {
+        return;
+    }
+
+    for chapter in chapters {
+        let extracted = (
+            chapter.number.to_owned(),
+            chapter.title.to_owned(),
+            chapter.url.to_owned(),
+        );
+        let get_extracted = |field: &ChapterField| -> &str {
+            match field {
+                ChapterField::Number => &extracted.0,
+                ChapterField::Title => &extracted.1,
+                ChapterField::Url => &extracted.2,
+            }
+        };
+
+        for (value, steps) in [
+            (&mut chapter.number, &transforms.number),
+            (&mut chapter.title, &transforms.title),
+            (&mut chapter.url, &transforms.url),
+        ] {
+            for step in steps {
+                *value = match step {
+                    TransformStep::From(field) => get_extracted(field).to_owned(),
+                    step => apply_step(step, value),
+                };
+            }
+        }
+    }
+}
+
+fn apply_step(step: &TransformStep, value: &str) -> String {
+    match step {
+        TransformStep::From(_) => value.to_owned(),
+        TransformStep::Regex(regex) => match regex.captures(value) {
+            Some(captures) => captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|capture| capture.as_str().to_owned())
+                .unwrap_or_default(),
+            None => value.to_owned(),
+        },
+        TransformStep::Replace(regex, with) => regex.replace_all(value, with.as_str()).into(),
+        TransformStep::Trim => value.trim().to_owned(),
+        TransformStep::CollapseWhitespace => value.split_whitespace().collect::<Vec<_>>().join(" "),
+        TransformStep::Lowercase => value.to_lowercase(),
+        TransformStep::Prefix(prefix) => format!("{}{}", prefix, value),
+        TransformStep::Suffix(suffix) => format!("{}{}", value, suffix),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+    use regex::Regex;
+
+    use crate::structs::{Chapter, ChapterField, Target, TargetTransforms, TransformStep};
+
+    use super::apply_transforms;
+
+    #[test]
+    fn test_apply_transforms() {
+        let target = Target {
+            transforms: TargetTransforms {
+                number: vec![
+                    TransformStep::From(ChapterField::Title),
+                    TransformStep::Regex(Regex::new(r"第(\d+)話").unwrap()),
+                    TransformStep::Prefix("Chapter ".into()),
+                ],
+                title: vec![
+                    TransformStep::Replace(Regex::new(r"【.*?】").unwrap(), "".into()),
+                    TransformStep::CollapseWhitespace,
+                ],
+                url: vec![
+                    TransformStep::Lowercase,
+                    TransformStep::Suffix("?lang=en".into()),
+                ],
+            },
+            ..Target::for_test("Test Manga", "https://comic-rss.com/feed.rss")
+        };
+
+        let now = Utc::now();
+        let mut chapters = vec![Chapter {
+            manga: "Test Manga".into(),
+            number: "episode-12".into(),
+            title: "【最新話】第12話 再会\n   ".into(),
+            date: now,
+            url: "https://comic-rss.com/Episode/12".into(),
+            thumbnail: None,
+            logged_at: None,
+            announced_at: now,
+        }];
+        apply_transforms(&target, &mut chapters);
+
+        assert_eq!(chapters[0].number, "Chapter 12");
+        assert_eq!(chapters[0].title, "第12話 再会");
+        assert_eq!(chapters[0].url, "https://comic-rss.com/episode/12?lang=en");
+    }
+}
@@ -1,13 +1,107 @@
-use url::Url;
-
-/// Helper that appends the target's base URL if the URL is relative
-pub fn make_link(base_url: &str, link: &str) -> String {
-    let url = match Url::parse(link) {
-        Ok(url) => url,
-        Err(_) => {
-            let merged = String::from(base_url) + link;
-            Url::parse(&merged).unwrap()
-        }
-    };
-    url.into()
-}
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use url::Url;
+
+/// Helper that appends the target's base URL if the URL is relative
+pub fn make_link(base_url: &str, link: &str) -> String {
+    let url = match Url::parse(link) {
+        Ok(url) => url,
+        Err(_) => {
+            let merged = String::from(base_url) + link;
+            Url::parse(&merged).unwrap()
+        }
+    };
+    url.into()
+}
+
+/// Helper that resolves an image URL against the target's base URL if it is relative.
+/// Returns `None` if the result isn't a valid HTTP(S) URL, since Discord rejects those in embeds.
+pub fn make_image_link(base_url: &Option<String>, link: &str) -> Option<String> {
+    let url = match (Url::parse(link), base_url) {
+        (Ok(url), _) => url,
+        (Err(_), Some(base_url)) => Url::parse(base_url).ok()?.join(link).ok()?,
+        (Err(_), None) => return None,
+    };
+
+    matches!(url.scheme(), "http" | "https").then(|| url.into())
+}
+
+/// Helper that reads a date/time written without an offset in the target's time zone (UTC if not set).
+/// Times repeated by a DST change resolve to the earliest one, and times skipped by one
+/// (like midnight in zones that change at 00:00) are moved forward an hour at a time until they exist.
+pub fn naive_to_utc(datetime: &NaiveDateTime, timezone: &Option<Tz>) -> Result<DateTime<Utc>> {
+    match timezone {
+        Some(timezone) => (0..=24)
+            .find_map(|hours| {
+                let shifted = datetime.checked_add_signed(Duration::hours(hours))?;
+                timezone.from_local_datetime(&shifted).earliest()
+            })
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .ok_or(anyhow!("{} does not exist in {}", datetime, timezone)),
+        None => Ok(datetime.and_utc()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, NaiveDate, TimeZone};
+
+    use super::{make_image_link, naive_to_utc};
+
+    #[test]
+    fn test_make_image_link() {
+        let base_url = Some(String::from("https://comic.example.com/series/"));
+
+        assert_eq!(
+            make_image_link(&None, "https://cdn.example.com/1.jpg").as_deref(),
+            Some("https://cdn.example.com/1.jpg"),
+        );
+        // Relative links are resolved against the base URL
+        assert_eq!(
+            make_image_link(&base_url, "/images/1.jpg").as_deref(),
+            Some("https://comic.example.com/images/1.jpg"),
+        );
+        assert_eq!(
+            make_image_link(&base_url, "thumb/1.jpg").as_deref(),
+            Some("https://comic.example.com/series/thumb/1.jpg"),
+        );
+        assert_eq!(make_image_link(&None, "/images/1.jpg"), None);
+        // Discord only accepts HTTP(S) images
+        assert_eq!(
+            make_image_link(&base_url, "data:image/png;base64,AAAA"),
+            None
+        );
+        assert_eq!(make_image_link(&None, "ftp://example.com/1.jpg"), None);
+    }
+
+    #[test]
+    fn test_naive_to_utc() {
+        let santiago = Some(chrono_tz::America::Santiago);
+        let convert = |date: NaiveDate, hour: u32| {
+            naive_to_utc(&date.and_hms_opt(hour, 0, 0).unwrap(), &santiago).unwrap()
+        };
+
+        assert_eq!(
+            convert(NaiveDate::from_ymd_opt(2023, 11, 2).unwrap(), 10),
+            DateTime::parse_from_rfc3339("2023-11-02T13:00:00+00:00").unwrap(),
+        );
+        // Clocks went from 00:00 to 01:00 on this day, so midnight doesn't exist
+        let midnight = NaiveDate::from_ymd_opt(2023, 9, 3)
+            .unwrap()
+            .and_hms_opt(0, 0, 0);
+        assert!(chrono_tz::America::Santiago
+            .from_local_datetime(&midnight.unwrap())
+            .earliest()
+            .is_none());
+        assert_eq!(
+            convert(NaiveDate::from_ymd_opt(2023, 9, 3).unwrap(), 0),
+            DateTime::parse_from_rfc3339("2023-09-03T04:00:00+00:00").unwrap(),
+        );
+        // Clocks went back from 00:00 to 23:00, the first 23:00 is used
+        assert_eq!(
+            convert(NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(), 23),
+            DateTime::parse_from_rfc3339("2023-04-02T02:00:00+00:00").unwrap(),
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value as JsonValue};
+use sxd_document::parser;
+use sxd_xpath::{nodeset::Node, Context, Factory, Value, XPath};
+
+use crate::{
+    config::reader::TableReader,
+    structs::{Chapter, ParseRules, Target, TargetPaths},
+};
+
+use super::{
+    json::parse_date_string,
+    parser::Parser,
+    readers::read_json_date_format,
+    transform::apply_transforms,
+    utils::{make_image_link, make_link},
+};
+
+/// Keys allowed in the `paths` table of a target.
+pub const TARGET_PATHS_KEYS: [&str; 9] = [
+    "chapters",
+    "number",
+    "title",
+    "date",
+    "dateFormat",
+    "url",
+    "thumbnail",
+    "namespaces",
+    "fields",
+];
+
+/// Compiles an XPath expression.
+pub fn make_xpath(expression: &str) -> Result<XPath> {
+    match Factory::new().build(expression) {
+        Ok(Some(xpath)) => Ok(xpath),
+        Ok(None) => bail!("Empty XPath expression"),
+        Err(error) => Err(anyhow!("{}", error)),
+    }
+}
+
+/// Gets the text of an expression evaluated at a node (for a node-set, the text of its first node).
+/// Returns `None` if the text is empty.
+fn get_value<'d>(context: &Context<'d>, node: Node<'d>, xpath: &XPath) -> Result<Option<String>> {
+    let value = xpath.evaluate(context, node)?.into_string();
+    let value = value.trim();
+    Ok((!value.is_empty()).then(|| value.to_owned()))
+}
+
+/// Gets the `paths` of a `xml` target.
+pub fn paths(rules: &ParseRules) -> Result<&TargetPaths> {
+    rules.get().ok_or(anyhow!("Target has no paths"))
+}
+
+pub fn parse_xml(target: &Target, source: &str) -> Result<Vec<Chapter>> {
+    let mut chapters: Vec<Chapter> = vec![];
+    let package = parser::parse(source)?;
+    let document = package.as_document();
+    let paths = paths(&target.rules)?;
+
+    let mut context = Context::new();
+    for (prefix, uri) in &paths.namespaces {
+        context.set_namespace(prefix, uri);
+    }
+
+    let compile = |expressions: &[String]| -> Result<Vec<XPath>> {
+        expressions
+            .iter()
+            .map(|expression| make_xpath(expression))
+            .collect()
+    };
+    let number_xpaths = compile(&paths.number)?;
+    let title_xpaths = compile(&paths.title)?;
+    let date_xpath = paths.date.as_deref().map(make_xpath).transpose()?;
+    let url_xpath = make_xpath(&paths.url)?;
+    let thumbnail_xpath = paths.thumbnail.as_deref().map(make_xpath).transpose()?;
+
+    let nodes = match make_xpath(&paths.chapters)?.evaluate(&context, document.root())? {
+        Value::Nodeset(nodes) => nodes.document_order(),
+        _ => bail!("{} does not select any nodes", paths.chapters),
+    };
+
+    for node in nodes {
+        let assemble_chapter = || -> Result<Chapter> {
+            let mixer = |xpaths: &[XPath], name: &str| -> Result<String> {
+                let mut vec = vec![];
+                for xpath in xpaths {
+                    let value = get_value(&context, node, xpath)?;
+                    vec.push(value.ok_or(anyhow!("No {} found", name))?);
+                }
+                Ok(vec.join(" "))
+            };
+
+            let number = mixer(&number_xpaths, "number")?;
+            let title = match &target.title_template {
+                Some(template) => {
+                    let title = match title_xpaths.is_empty() {
+                        true => None,
+                        false => mixer(&title_xpaths, "title").ok(),
+                    };
+                    template.render(|name| match name {
+                        "number" => Some(number.to_owned()),
+                        "title" => title.to_owned(),
+                        name => paths
+                            .fields
+                            .get(name)
+                            .and_then(|expression| make_xpath(expression).ok())
+                            .and_then(|xpath| get_value(&context, node, &xpath).ok().flatten()),
+                    })
+                }
+                None => mixer(&title_xpaths, "title")?,
+            };
+
+            let date = match &date_xpath {
+                Some(xpath) => {
+                    let date = get_value(&context, node, xpath)?.ok_or(anyhow!("No date found"))?;
+                    parse_date_string(&date, &paths.date_format, &target.timezone)?
+                }
+                None => Utc::now(),
+            };
+
+            let url = get_value(&context, node, &url_xpath)?.ok_or(anyhow!("No URL found"))?;
+
+            let thumbnail = match &thumbnail_xpath {
+                Some(xpath) => get_value(&context, node, xpath)
+                    .ok()
+                    .flatten()
+                    .and_then(|link| make_image_link(&target.base_url, &link)),
+                None => None,
+            };
+
+            Ok(Chapter {
+                manga: target.name.to_owned(),
+                number,
+                title,
+                date,
+                url: match &target.base_url {
+                    Some(base_url) => make_link(base_url, &url),
+                    None => url,
+                },
+                thumbnail,
+                logged_at: None,
+                announced_at: date + Duration::days(target.delay.unwrap_or(0).into()),
+            })
+        };
+
+        // Like with HTML sources, nodes missing a value are not chapters
+        if let Ok(chapter) = assemble_chapter() {
+            chapters.push(chapter);
+        }
+    }
+
+    apply_transforms(target, &mut chapters);
+
+    if !target.ascending_source {
+        chapters.reverse();
+    }
+
+    Ok(chapters)
+}
+
+/// Parser of `mode = "xml"`, reading the chapters with the XPath expressions of its `paths` table.
+pub struct XmlParser;
+
+impl Parser for XmlParser {
+    fn name(&self) -> &'static str {
+        "xml"
+    }
+
+    fn tables(&self) -> &'static [&'static str] {
+        &["paths"]
+    }
+
+    fn table_schema(&self, _table: &str) -> JsonValue {
+        paths_schema()
+    }
+
+    fn read_rules(&self, target: &TableReader, has_template: bool) -> Option<ParseRules> {
+        let paths = read_paths(&target.table("paths")?, has_template)?;
+        Some(ParseRules::new(paths))
+    }
+
+    fn has_field(&self, rules: &ParseRules, name: &str) -> bool {
+        paths(rules).is_ok_and(|paths| paths.fields.contains_key(name))
+    }
+
+    fn parse(&self, target: &Target, body: &str) -> Result<Vec<Chapter>> {
+        parse_xml(target, body)
+    }
+}
+
+/// Reads the XPath expressions for a target that has a XML source.
+/// `title` is optional when the target has a title template.
+fn read_paths(reader: &TableReader, has_template: bool) -> Option<TargetPaths> {
+    reader.deny_unknown_keys(&TARGET_PATHS_KEYS);
+
+    let chapters = reader.required_string("chapters");
+    let number = reader.required_strings("number");
+    let title = match has_template && !reader.has("title") {
+        true => Some(vec![]),
+        false => reader.required_strings("title"),
+    };
+    let date = reader.non_empty_string("date");
+    let date_format = read_json_date_format(reader);
+    let url = reader.required_string("url");
+    let thumbnail = reader.non_empty_string("thumbnail");
+    let namespaces = reader.string_table("namespaces");
+    let fields = reader.string_table("fields");
+
+    let mut valid = true;
+    for (key, expressions) in [
+        ("chapters", chapters.iter().cloned().collect()),
+        ("number", number.clone().unwrap_or_default()),
+        ("title", title.clone().unwrap_or_default()),
+        ("date", date.iter().cloned().collect()),
+        ("url", url.iter().cloned().collect()),
+        ("thumbnail", thumbnail.iter().cloned().collect()),
+    ] {
+        valid &= check_xpaths(reader, key, &expressions);
+    }
+    if let (Some(fields), Some(fields_reader)) = (&fields, reader.table("fields")) {
+        for (name, expression) in fields {
+            valid &= check_xpaths(&fields_reader, name, &[expression.to_owned()]);
+        }
+    }
+    if !valid {
+        return None;
+    }
+
+    Some(TargetPaths {
+        chapters: chapters?,
+        number: number?,
+        title: title?,
+        date,
+        date_format,
+        url: url?,
+        thumbnail,
+        namespaces: namespaces?,
+        fields: fields?,
+    })
+}
+
+/// Reports every expression that is not a valid XPath. Returns whether all of them are.
+fn check_xpaths(reader: &TableReader, key: &str, expressions: &[String]) -> bool {
+    let mut valid = true;
+    for expression in expressions {
+        if let Err(error) = make_xpath(expression) {
+            reader.error(
+                key,
+                format!("is not a valid XPath (\"{}\": {})", expression, error),
+            );
+            valid = false;
+        }
+    }
+
+    valid
+}
+
+fn paths_schema() -> JsonValue {
+    let strings = json!({
+        "oneOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } },
+        ],
+    });
+    let names = json!({
+        "type": "object",
+        "additionalProperties": { "type": "string" },
+    });
+
+    json!({
+        "type": "object",
+        "description": "XPath expressions of the chapter values in a XML source, relative to each chapter's node.",
+        "additionalProperties": false,
+        "required": ["chapters", "number", "url"],
+        "properties": {
+            "chapters": { "type": "string", "description": "Selects the node of every chapter." },
+            "number": strings.clone(),
+            "title": strings,
+            "date": { "type": "string" },
+            "dateFormat": {
+                "type": "string",
+                "description": "\"unixsec\", \"unix\", \"unixmilli\", \"unixnano\", \"rfc2822\", \"rfc3339\", \"relative\" (e.g. 3日前, 2 hours ago) or a strftime format.",
+            },
+            "url": { "type": "string" },
+            "thumbnail": { "type": "string" },
+            "namespaces": names.clone(),
+            "fields": names,
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::DateTime;
+
+    use crate::structs::{
+        JsonDateTimeFormat::StringFormat, ParseMode, ParseRules, Target, TargetPaths,
+    };
+
+    use super::{parse_xml, XmlParser};
+
+    #[test]
+    fn test_parse_xml() {
+        let target = Target {
+            mode: ParseMode(&XmlParser),
+            base_url: Some("https://comic-xml.com".into()),
+            rules: ParseRules::new(TargetPaths {
+                chapters: "//c:row[@type = 'episode']".into(),
+                number: vec!["@id".into()],
+                title: vec!["c:title".into()],
+                date: Some("following-sibling::c:row[1][@type = 'date']".into()),
+                date_format: Some(StringFormat("%Y/%m/%d".into())),
+                url: "c:link/@href".into(),
+                thumbnail: Some("c:image".into()),
+                namespaces: HashMap::from([(
+                    String::from("c"),
+                    String::from("https://comic-xml.com/schema"),
+                )]),
+                fields: HashMap::new(),
+            }),
+            ..Target::for_test("Test Manga", "https://comic-xml.com/api/episodes.xml")
+        };
+
+        let source = r###"<?xml version="1.0" encoding="UTF-8"?>
+        <list xmlns="https://comic-xml.com/schema">
+            <row type="episode" id="24">
+                <title>第24話</title>
+                <link href="/episode/24" />
+                <image>https://cdn.comic-xml.com/24.jpg</image>
+            </row>
+            <row type="date">2023/11/09</row>
+            <row type="episode" id="23">
+                <title>第23話</title>
+                <link href="/episode/23" />
+            </row>
+            <row type="date">2023/11/02</row>
+            <row type="episode" id="22">
+                <title>第22話</title>
+            </row>
+            <row type="date">2023/10/26</row>
+        </list>"###;
+        let chapters = parse_xml(&target, source).unwrap();
+
+        // The episode without a link is skipped
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].number, "23");
+        assert_eq!(chapters[1].number, "24");
+        assert_eq!(chapters[0].title, "第23話");
+        assert_eq!(chapters[0].url, "https://comic-xml.com/episode/23");
+        // Dates come from the row after each episode
+        assert_eq!(
+            chapters[0].date,
+            DateTime::parse_from_rfc3339("2023-11-02T00:00:00+00:00").unwrap(),
+        );
+        assert_eq!(
+            chapters[1].date,
+            DateTime::parse_from_rfc3339("2023-11-09T00:00:00+00:00").unwrap(),
+        );
+        assert_eq!(chapters[0].thumbnail, None);
+        assert_eq!(
+            chapters[1].thumbnail.as_deref(),
+            Some("https://cdn.comic-xml.com/24.jpg"),
+        );
+    }
+}
@@ -1,97 +1,590 @@
-use std::collections::HashMap;
-
-use chrono::prelude::*;
-use serde_json::Value;
-
-/// Contains information of a Server that's registered to the bot.
-#[derive(Debug, Clone)]
-pub struct Server {
-    pub identifier: String,
-    pub feed_channel_identifier: String,
-}
-
-impl PartialEq for Server {
-    fn eq(&self, other: &Self) -> bool {
-        self.identifier == other.identifier
-    }
-}
-
-/// Chapter of a manga.
-/// `number` may not be the same as the chapter's release number,
-/// just an unique identifier to set it apart from other chapters.
-#[derive(Debug, Clone)]
-pub struct Chapter {
-    pub manga: String,
-    pub number: String,
-    pub title: String,
-    pub date: DateTime<Utc>,
-    pub url: String,
-    pub logged_at: Option<DateTime<Utc>>,
-    pub announced_at: DateTime<Utc>,
-}
-
-/// Supported parse modes.
-#[derive(Debug, Clone)]
-pub enum ParseMode {
-    Rss,
-    Json,
-    Html,
-    JsonInHtml,
-}
-
-/// Each target defines a source to get manga updates from.
-#[derive(Debug, Clone)]
-pub struct Target {
-    pub name: String,
-    pub source: String,
-    /// Whether the source lists item A->Z (old chapters first) instead of Z->A (new chapters first).
-    pub ascending_source: bool,
-    pub mode: ParseMode,
-    pub base_url: Option<String>,
-    pub request_headers: Option<HashMap<String, String>>,
-    /// How much time to delay the announcement of new chapters (in days).
-    pub delay: Option<u8>,
-    // JSON mode
-    pub keys: Option<TargetKeys>,
-    // HTML mode
-    pub tags: Option<TargetTags>,
-}
-
-/// Enum of supported datetime parse formats for the JSON parser.
-#[derive(Debug, Clone)]
-pub enum JsonDateTimeFormat {
-    UnixSec,
-    UnixMilli,
-    UnixNano,
-    Rfc2822,
-    Rfc3339,
-    StringFormat(String),
-}
-
-/// JSON object keys information for parsing from a JSON source.
-#[derive(Debug, Clone)]
-pub struct TargetKeys {
-    pub chapters: String,
-    pub number: Vec<String>,
-    pub title: Vec<String>,
-    pub date: String,
-    pub date_format: Option<JsonDateTimeFormat>,
-    pub url: String,
-    pub skip: HashMap<String, Value>,
-}
-
-/// Strings of tag and attribute names for parsing from a HTML source.
-#[derive(Debug, Clone)]
-pub struct TargetTags {
-    pub chapters_tag: String,
-    pub number_tag: Option<String>,
-    pub number_attribute: Option<String>,
-    pub title_tag: Option<String>,
-    pub title_attribute: Option<String>,
-    pub date_tag: Option<String>,
-    pub date_attribute: Option<String>,
-    /// How to parse the date text. Uses strftime format notation.
-    pub date_format: Option<String>,
-    pub url_tag: Option<String>,
-    pub url_attribute: Option<String>,
-}
+use std::{
+    any::Any,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+use chrono::prelude::*;
+use chrono_tz::Tz;
+use regex::Regex;
+use scraper::Selector;
+use serde_json::Value;
+
+use crate::parsers::{json_path::JsonPath, parser::Parser, template::TitleTemplate};
+
+/// Contains information of a Server that's registered to the bot.
+#[derive(Debug, Clone)]
+pub struct Server {
+    pub identifier: String,
+    pub feed_channel_identifier: String,
+}
+
+impl PartialEq for Server {
+    fn eq(&self, other: &Self) -> bool {
+        self.identifier == other.identifier
+    }
+}
+
+/// Chapter of a manga.
+/// `number` may not be the same as the chapter's release number,
+/// just an unique identifier to set it apart from other chapters.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub manga: String,
+    pub number: String,
+    pub title: String,
+    pub date: DateTime<Utc>,
+    pub url: String,
+    /// URL of the chapter's cover or thumbnail image, if the source has one.
+    pub thumbnail: Option<String>,
+    #[allow(dead_code)]
+    pub logged_at: Option<DateTime<Utc>>,
+    pub announced_at: DateTime<Utc>,
+}
+
+/// Parse mode of a target, one of the parsers in `PARSERS`.
+#[derive(Clone, Copy)]
+pub struct ParseMode(pub &'static dyn Parser);
+
+impl ParseMode {
+    /// Value of `mode` that picks this parser.
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    pub fn parser(&self) -> &'static dyn Parser {
+        self.0
+    }
+}
+
+impl Debug for ParseMode {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_tuple("ParseMode")
+            .field(&self.name())
+            .finish()
+    }
+}
+
+impl PartialEq for ParseMode {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+/// Rules read by the parser of a target from its tables, of a type only that parser knows
+/// (e.g. `TargetKeys` for `json` mode). Each parser module has a getter for its own type.
+/// They are compared with the `PartialEq` of that type, so reloads can tell whether they changed.
+#[derive(Clone)]
+pub struct ParseRules {
+    rules: Arc<dyn Any + Send + Sync>,
+    eq: fn(&dyn Any, &dyn Any) -> bool,
+}
+
+impl ParseRules {
+    pub fn new<T: Any + Send + Sync + PartialEq>(rules: T) -> Self {
+        Self {
+            rules: Arc::new(rules),
+            eq: |a, b| a.downcast_ref::<T>() == b.downcast_ref::<T>(),
+        }
+    }
+
+    /// Gets the rules if they are of type `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.rules.downcast_ref()
+    }
+}
+
+impl Debug for ParseRules {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("ParseRules(..)")
+    }
+}
+
+impl PartialEq for ParseRules {
+    fn eq(&self, other: &Self) -> bool {
+        (self.eq)(self.rules.as_ref(), other.rules.as_ref())
+    }
+}
+
+/// Each target defines a source to get manga updates from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub source: String,
+    /// Whether the source lists item A->Z (old chapters first) instead of Z->A (new chapters first).
+    pub ascending_source: bool,
+    pub mode: ParseMode,
+    pub base_url: Option<String>,
+    pub request_headers: Option<HashMap<String, String>>,
+    /// How much time to delay the announcement of new chapters (in days).
+    pub delay: Option<u8>,
+    /// Time zone of the dates written without an offset (UTC if `None`).
+    pub timezone: Option<Tz>,
+    /// Fetch schedule for this target, overriding the global `cron`.
+    pub cron: Option<String>,
+    /// Disabled targets are neither fetched nor announced.
+    pub enabled: bool,
+    /// Guild IDs of the only Servers this target is announced on (every Server if `None`).
+    pub servers: Option<Vec<String>>,
+    /// Guild IDs of Servers this target is never announced on.
+    pub exclude_servers: Option<Vec<String>>,
+    /// Rules read by the parser of the mode from its tables.
+    pub rules: ParseRules,
+    /// How to fetch the following pages of a paginated source.
+    pub pagination: Option<TargetPagination>,
+    /// Rules for the page of each new chapter, fetched after the chapter list.
+    pub detail: Option<TargetDetail>,
+    /// Post-processing steps for the extracted chapter fields.
+    pub transforms: TargetTransforms,
+    /// Format of the chapter title, filled in with the named fields of the chapter.
+    pub title_template: Option<TitleTemplate>,
+}
+
+impl Target {
+    /// Whether new chapters of this target should be announced on a certain Server.
+    pub fn is_announced_on(&self, guild_id: &str) -> bool {
+        let included = match &self.servers {
+            Some(servers) => servers.iter().any(|server| server == guild_id),
+            None => true,
+        };
+        let excluded = match &self.exclude_servers {
+            Some(servers) => servers.iter().any(|server| server == guild_id),
+            None => false,
+        };
+
+        self.enabled && included && !excluded
+    }
+}
+
+#[cfg(test)]
+impl Target {
+    /// A plain enabled RSS target, for tests that only care about a few of its fields.
+    pub fn for_test(name: &str, source: &str) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+            ascending_source: false,
+            mode: ParseMode(&crate::parsers::rss::RssParser),
+            base_url: None,
+            request_headers: None,
+            delay: None,
+            timezone: None,
+            cron: None,
+            enabled: true,
+            servers: None,
+            exclude_servers: None,
+            rules: ParseRules::new(TargetFeed::default()),
+            pagination: None,
+            detail: None,
+            transforms: TargetTransforms::default(),
+            title_template: None,
+        }
+    }
+}
+
+/// XPath expressions for parsing from a XML source.
+/// Every expression but `chapters` is evaluated with the chapter's node as the context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetPaths {
+    pub chapters: String,
+    pub number: Vec<String>,
+    pub title: Vec<String>,
+    /// Uses the time of the fetch if not set.
+    pub date: Option<String>,
+    pub date_format: Option<JsonDateTimeFormat>,
+    pub url: String,
+    pub thumbnail: Option<String>,
+    /// Namespace URIs of the prefixes used in the expressions.
+    pub namespaces: HashMap<String, String>,
+    /// Extra named values for `titleTemplate`.
+    pub fields: HashMap<String, String>,
+}
+
+/// Enum of supported datetime parse formats for the JSON parser.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonDateTimeFormat {
+    UnixSec,
+    UnixMilli,
+    UnixNano,
+    Rfc2822,
+    Rfc3339,
+    StringFormat(String),
+}
+
+impl JsonDateTimeFormat {
+    /// Parses a `dateFormat` value. Anything that isn't a known name is a strftime format.
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "unixsec" => JsonDateTimeFormat::UnixSec,
+            "unix" | "unixmilli" => JsonDateTimeFormat::UnixMilli,
+            "unixnano" => JsonDateTimeFormat::UnixNano,
+            "rfc2822" => JsonDateTimeFormat::Rfc2822,
+            "rfc3339" => JsonDateTimeFormat::Rfc3339,
+            _ => JsonDateTimeFormat::StringFormat(name.to_owned()),
+        }
+    }
+}
+
+/// A condition on the values of a chapter of a JSON source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonCondition {
+    /// Holds if any of the conditions holds.
+    Any(Vec<JsonCondition>),
+    /// Holds if every condition holds.
+    All(Vec<JsonCondition>),
+    /// Tests the value at a path inside the chapter.
+    Value { path: JsonPath, test: ValueTest },
+}
+
+/// A test on a single value of a JSON chapter.
+#[derive(Debug, Clone)]
+pub enum ValueTest {
+    /// The value is set and equal. This is the test of a plain `path = value`.
+    Equals(Value),
+    /// The value is missing or different.
+    NotEquals(Value),
+    /// The value is set and not `null`.
+    Exists,
+    Missing,
+    /// The value (a string, number or boolean) matches the regex.
+    Matches(Regex),
+    /// The value is a number (or a string of one) that is less or greater than the operand,
+    /// or equal to it with `or_equal`.
+    Compare {
+        ordering: Ordering,
+        or_equal: bool,
+        operand: f64,
+    },
+    /// The value is a date, read with `format` (or the `dateFormat` of the keys), before or after a moment.
+    Date {
+        after: bool,
+        moment: DateOperand,
+        format: Option<JsonDateTimeFormat>,
+    },
+}
+
+impl PartialEq for ValueTest {
+    fn eq(&self, other: &Self) -> bool {
+        use ValueTest::*;
+
+        match (self, other) {
+            (Equals(a), Equals(b)) | (NotEquals(a), NotEquals(b)) => a == b,
+            (Exists, Exists) | (Missing, Missing) => true,
+            (Matches(a), Matches(b)) => a.as_str() == b.as_str(),
+            (
+                Compare {
+                    ordering: a,
+                    or_equal: a_or_equal,
+                    operand: a_operand,
+                },
+                Compare {
+                    ordering: b,
+                    or_equal: b_or_equal,
+                    operand: b_operand,
+                },
+            ) => a == b && a_or_equal == b_or_equal && a_operand == b_operand,
+            (
+                Date {
+                    after: a,
+                    moment: a_moment,
+                    format: a_format,
+                },
+                Date {
+                    after: b,
+                    moment: b_moment,
+                    format: b_format,
+                },
+            ) => a == b && a_moment == b_moment && a_format == b_format,
+            _ => false,
+        }
+    }
+}
+
+/// The moment a date is compared to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateOperand {
+    /// The time of the fetch.
+    Now,
+    At(DateTime<Utc>),
+}
+
+/// JSON object keys information for parsing from a JSON source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetKeys {
+    pub chapters: JsonPath,
+    pub number: Vec<JsonPath>,
+    pub title: Vec<JsonPath>,
+    pub date: JsonPath,
+    pub date_format: Option<JsonDateTimeFormat>,
+    pub url: JsonPath,
+    /// Chapters are skipped when any of these conditions holds.
+    pub skip: Vec<JsonCondition>,
+    /// Chapters are skipped unless every one of these conditions holds.
+    pub include: Vec<JsonCondition>,
+    pub thumbnail: Option<JsonPath>,
+    /// Extra named values for `titleTemplate`, as paths inside each chapter.
+    pub fields: HashMap<String, JsonPath>,
+}
+
+/// Strings of tag and attribute names for parsing from a HTML source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetTags {
+    pub chapters_tag: String,
+    pub number_tag: Option<String>,
+    pub number_attribute: Option<String>,
+    pub title_tag: Option<String>,
+    pub title_attribute: Option<String>,
+    pub date_tag: Option<String>,
+    pub date_attribute: Option<String>,
+    /// How to parse the date text. Uses strftime format notation.
+    pub date_format: Option<String>,
+    pub url_tag: Option<String>,
+    pub url_attribute: Option<String>,
+    /// Chapters have no thumbnail if neither the tag nor the attribute is set.
+    pub thumbnail_tag: Option<String>,
+    pub thumbnail_attribute: Option<String>,
+    /// Where the JSON starts inside the script text (`json_in_html` mode).
+    /// Without it, the whole text is parsed.
+    pub script: Option<ScriptLocator>,
+    /// Chapter elements matching any of these rules are skipped.
+    pub skip: Vec<HtmlSkipRule>,
+    /// Extra named values for `titleTemplate`.
+    pub fields: HashMap<String, HtmlField>,
+}
+
+/// How to find the data assigned inside a script, e.g. `window.__INITIAL_STATE__ = {...};`.
+#[derive(Debug, Clone)]
+pub enum ScriptLocator {
+    /// The data follows this text.
+    Marker(String),
+    /// The data starts at the first capture group, or follows the whole match.
+    Pattern(Regex),
+}
+
+impl PartialEq for ScriptLocator {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Marker(a), Self::Marker(b)) => a == b,
+            (Self::Pattern(a), Self::Pattern(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+/// Where to find a value inside a chapter element of a HTML source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlField {
+    pub tag: Option<String>,
+    pub attribute: Option<String>,
+}
+
+/// A rule for skipping chapter elements of a HTML source, like locked or upcoming chapters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlSkipRule {
+    /// Matched against the element itself and its descendants.
+    pub tag: Option<Selector>,
+    pub attribute: Option<String>,
+    /// Without a condition, the element is skipped when the tag (and attribute) is found.
+    pub condition: Option<SkipCondition>,
+}
+
+/// Condition on the text (or attribute value) of a skip rule.
+#[derive(Debug, Clone)]
+pub enum SkipCondition {
+    /// The trimmed text is exactly this value.
+    Equals(String),
+    Matches(Regex),
+}
+
+impl PartialEq for SkipCondition {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Equals(a), Self::Equals(b)) => a == b,
+            (Self::Matches(a), Self::Matches(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+/// Chapter fields that can be post-processed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChapterField {
+    Number,
+    Title,
+    Url,
+}
+
+/// A single post-processing step for a chapter field.
+#[derive(Debug, Clone)]
+pub enum TransformStep {
+    /// Replaces the value with the extracted (untransformed) value of another field.
+    From(ChapterField),
+    /// Keeps the first capture group of the match (or the whole match if there are no groups).
+    /// The value is left as is if it doesn't match.
+    Regex(Regex),
+    /// Replaces every match. The replacement can refer to capture groups with `$1`.
+    Replace(Regex, String),
+    Trim,
+    /// Trims the value and turns every run of whitespace into a single space.
+    CollapseWhitespace,
+    Lowercase,
+    Prefix(String),
+    Suffix(String),
+}
+
+impl PartialEq for TransformStep {
+    fn eq(&self, other: &Self) -> bool {
+        use TransformStep::*;
+
+        match (self, other) {
+            (From(a), From(b)) => a == b,
+            (Regex(a), Regex(b)) => a.as_str() == b.as_str(),
+            (Replace(a, a_with), Replace(b, b_with)) => {
+                a.as_str() == b.as_str() && a_with == b_with
+            }
+            (Trim, Trim) | (CollapseWhitespace, CollapseWhitespace) | (Lowercase, Lowercase) => {
+                true
+            }
+            (Prefix(a), Prefix(b)) | (Suffix(a), Suffix(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Post-processing steps for each chapter field, applied in order after extraction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetTransforms {
+    pub number: Vec<TransformStep>,
+    pub title: Vec<TransformStep>,
+    pub url: Vec<TransformStep>,
+}
+
+/// A value of a feed entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedField {
+    /// The `guid` (RSS) or `id` (Atom).
+    Id,
+    Title,
+    /// The `description` (RSS) or `summary` (Atom).
+    Summary,
+    Content,
+    /// The first link, or the first one with the given `rel`.
+    Link(Option<String>),
+    /// The URL of the first enclosure or media content.
+    Enclosure,
+    /// The URL of the first media thumbnail (`media:thumbnail`).
+    Thumbnail,
+    Published,
+    Updated,
+    /// The text of a namespaced child element of the entry, e.g. `giga:episodeNumber`.
+    Extension(String),
+}
+
+impl FeedField {
+    /// Parses a field name such as `guid`, `link:alternate` or `giga:episodeNumber`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let field = match name {
+            "id" | "guid" => FeedField::Id,
+            "title" => FeedField::Title,
+            "summary" | "description" => FeedField::Summary,
+            "content" => FeedField::Content,
+            "link" => FeedField::Link(None),
+            "enclosure" => FeedField::Enclosure,
+            "thumbnail" | "media:thumbnail" => FeedField::Thumbnail,
+            "published" | "pubDate" => FeedField::Published,
+            "updated" => FeedField::Updated,
+            name => match name.split_once(':') {
+                Some(("link", rel)) if !rel.is_empty() => FeedField::Link(Some(rel.to_owned())),
+                Some((prefix, local)) if !prefix.is_empty() && !local.is_empty() => {
+                    FeedField::Extension(name.to_owned())
+                }
+                _ => return None,
+            },
+        };
+
+        Some(field)
+    }
+}
+
+/// Which feed fields provide each chapter value for a RSS/Atom source.
+/// Every value is a list of fields, the first one present in the entry is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetFeed {
+    pub number: Vec<FeedField>,
+    pub title: Vec<FeedField>,
+    /// Uses the time of the fetch if none of the fields are present.
+    pub date: Vec<FeedField>,
+    pub url: Vec<FeedField>,
+    /// Chapters have no thumbnail if none of the fields are present.
+    pub thumbnail: Vec<FeedField>,
+    /// Whether entries without a title are skipped (otherwise the whole feed fails to parse).
+    pub skip_untitled: bool,
+}
+
+impl Default for TargetFeed {
+    fn default() -> Self {
+        Self {
+            number: vec![FeedField::Id],
+            title: vec![FeedField::Title],
+            date: vec![FeedField::Published],
+            url: vec![FeedField::Link(None)],
+            thumbnail: vec![FeedField::Thumbnail, FeedField::Enclosure],
+            skip_untitled: true,
+        }
+    }
+}
+
+/// How to get the URL of the next page of a paginated source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageStrategy {
+    /// Sets a query parameter of `source` to the page number, counting up from `start`.
+    Number { parameter: String, start: u32 },
+    /// Follows the link in the first element matching `tag` (HTML sources).
+    NextLink { tag: String, attribute: String },
+    /// Reads a cursor at a path of the JSON response. With a `parameter` it's set as that
+    /// query parameter of `source`, otherwise the cursor is the URL of the next page itself.
+    Cursor {
+        path: JsonPath,
+        parameter: Option<String>,
+    },
+}
+
+/// Pagination of a source whose chapter list is split across several pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetPagination {
+    pub strategy: PageStrategy,
+    /// Most pages fetched in a run, including the first one.
+    pub max_pages: u32,
+    /// Whether to stop at the first page that only has chapters saved already.
+    pub stop_on_known: bool,
+}
+
+/// Rules for extracting values from the page of a single chapter.
+/// Values that are not set or not found keep what was extracted from the chapter list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DetailRules {
+    Html {
+        title: Option<HtmlField>,
+        date: Option<HtmlField>,
+        /// How to parse the date text. Uses strftime format notation.
+        date_format: Option<String>,
+        thumbnail: Option<HtmlField>,
+    },
+    Json {
+        title: Option<JsonPath>,
+        date: Option<JsonPath>,
+        date_format: Option<JsonDateTimeFormat>,
+        thumbnail: Option<JsonPath>,
+    },
+}
+
+/// Second stage of a fetch, where the page of each new chapter is fetched to fill in its values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetDetail {
+    pub rules: DetailRules,
+    /// Most chapter pages fetched in a run. New chapters past the limit are left for the next run.
+    pub limit: u32,
+}